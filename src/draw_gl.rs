@@ -3,8 +3,9 @@ use gl::types::*;
 use image::DynamicImage;
 use image::RgbaImage;
 use std::collections::HashMap;
-use std::ffi::CString;
-use std::os::raw::c_void;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_void};
+use std::path::Path;
 use std::ptr;
 
 // Shader
@@ -268,10 +269,15 @@ impl Texturs {
     }
 
     pub fn load_file(&mut self, filename: &str) {
-        let name = filename.to_string();
+        self.load_file_as(filename, Path::new(filename));
+    }
+
+    // 登録名とは別の場所にあるファイルを読み込む
+    pub fn load_file_as(&mut self, name: &str, path: &Path) {
+        let name = name.to_string();
         match self.textures.get(&name) {
             None => {
-                let img = image::io::Reader::open(&name).unwrap().decode().unwrap();
+                let img = image::io::Reader::open(path).unwrap().decode().unwrap();
                 let texture = Textur::loda_image(&img);
                 self.textures.insert(name, texture);
             }
@@ -359,3 +365,132 @@ impl Drop for VertexArrayBuffer {
         }
     }
 }
+
+// 拡張機能の有無
+#[allow(dead_code)]
+pub fn has_extension(name: &str) -> bool {
+    unsafe {
+        let ptr = gl::GetString(gl::EXTENSIONS);
+        if ptr.is_null() {
+            return false;
+        }
+        CStr::from_ptr(ptr as *const c_char)
+            .to_string_lossy()
+            .split_whitespace()
+            .any(|ext| ext == name)
+    }
+}
+
+// 32bitインデックスが使えるか(GLES 2.0 では OES_element_index_uint が必要)
+#[allow(dead_code)]
+pub fn supports_element_index_uint() -> bool {
+    let version = unsafe {
+        let ptr = gl::GetString(gl::VERSION);
+        if ptr.is_null() {
+            return false;
+        }
+        CStr::from_ptr(ptr as *const c_char).to_string_lossy().into_owned()
+    };
+    if version.starts_with("OpenGL ES 2.") {
+        has_extension("GL_OES_element_index_uint")
+    } else {
+        true
+    }
+}
+
+pub struct ElementArrayBuffer {
+    ebo: GLuint,
+    index_type: GLenum, // 転送したインデックスの型
+    count: usize,       // 転送したインデックス数
+}
+
+#[allow(dead_code)]
+impl ElementArrayBuffer {
+    pub fn new() -> Self {
+        let mut element_array = ElementArrayBuffer {
+            ebo: 0,
+            index_type: gl::UNSIGNED_SHORT,
+            count: 0,
+        };
+        unsafe {
+            gl::GenBuffers(1, &mut element_array.ebo);
+        }
+        element_array
+    }
+
+    pub fn bind_buffer(&self) {
+        unsafe {
+            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.ebo);
+        }
+    }
+
+    pub fn get_count(&self) -> usize {
+        self.count
+    }
+
+    fn buffer_data<I>(&mut self, indices: &[I], index_type: GLenum, usage: GLenum) {
+        self.bind_buffer();
+        unsafe {
+            gl::BufferData(
+                gl::ELEMENT_ARRAY_BUFFER,
+                std::mem::size_of_val(indices) as isize,
+                indices.as_ptr() as *const c_void,
+                usage,
+            );
+        }
+        self.index_type = index_type;
+        self.count = indices.len();
+    }
+
+    pub fn buffer_data_u8(&mut self, indices: &[u8], usage: GLenum) {
+        self.buffer_data(indices, gl::UNSIGNED_BYTE, usage);
+    }
+
+    pub fn buffer_data_u16(&mut self, indices: &[u16], usage: GLenum) {
+        self.buffer_data(indices, gl::UNSIGNED_SHORT, usage);
+    }
+
+    pub fn buffer_data_u32(&mut self, indices: &[u32], usage: GLenum) -> Result<(), String> {
+        if !supports_element_index_uint() {
+            return Err("32bit index is not supported (GL_OES_element_index_uint)".to_string());
+        }
+        self.buffer_data(indices, gl::UNSIGNED_INT, usage);
+        Ok(())
+    }
+
+    // glDrawElements (start, count はインデックス単位、範囲外は panic)
+    pub fn draw_elements(&self, mode: GLenum, start: usize, count: usize) {
+        assert!(
+            start + count <= self.count,
+            "index range out of bounds: {}+{} > {}",
+            start,
+            count,
+            self.count
+        );
+        let unit_size = match self.index_type {
+            gl::UNSIGNED_BYTE => 1,
+            gl::UNSIGNED_SHORT => 2,
+            _ => 4,
+        };
+        self.bind_buffer();
+        unsafe {
+            gl::DrawElements(
+                mode,
+                count as GLsizei,
+                self.index_type,
+                (start * unit_size) as *const c_void,
+            );
+        }
+    }
+}
+
+impl Drop for ElementArrayBuffer {
+    #[allow(dead_code)]
+    fn drop(&mut self) {
+        if self.ebo > 0 {
+            unsafe {
+                gl::DeleteBuffers(1, &self.ebo);
+            }
+        }
+    }
+}
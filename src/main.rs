@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::time::Duration;
 
 use cgmath::perspective;
@@ -21,15 +22,29 @@ type Matrix4 = cgmath::Matrix4<f32>;
 
 fn main() {
     // メッシュ準備
-    let mesh: Box<mesh_obj::Mesh<f32>>;
-    let mesh_scale: f32;
-    if false {
-        mesh = mesh_obj::Mesh::<f32>::load("miku.obj").unwrap();
-        mesh_scale = 10.0;
+    let mesh: Box<mesh_obj::Mesh<f32>> = if false {
+        mesh_obj::Mesh::<f32>::load("miku.obj").unwrap()
     } else {
-        mesh = mesh_obj::Mesh::<f32>::load("unity_chan.obj").unwrap();
-        mesh_scale = 300.0;
-    }
+        mesh_obj::Mesh::<f32>::load("unity_chan.obj").unwrap()
+    };
+
+    // 境界球からカメラ距離とクリップ面を決める
+    let field_of_view: f32 = 45.0;
+    let bounding_sphere = mesh
+        .get_bounding_sphere()
+        .unwrap_or(mesh_obj::BoundingSphere {
+            center: mesh_obj::Vecter3D {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            radius: 1.0,
+        });
+    let look_center = bounding_sphere.center;
+    let look_radius = bounding_sphere.radius.max(f32::EPSILON);
+    let camera_distance = look_radius / (field_of_view.to_radians() * 0.5).sin() * 1.1;
+    let clip_near = (camera_distance - look_radius).max(camera_distance * 0.01);
+    let clip_far = camera_distance + look_radius * 2.0;
 
     let window_width: u32 = 640;
     let window_height: u32 = 480;
@@ -71,21 +86,41 @@ fn main() {
     let attrib_normal = program.get_attrib_location("normal");
     let attrib_texcoord = program.get_attrib_location("texcoord");
 
+    // 頂点/インデックスバッファ転送
+    //  32bitインデックスが使えれば1バッチ、使えなければ16bitインデックスで分割
+    let mut batches = Vec::new();
+    if draw_gl::supports_element_index_uint() {
+        let arrays = mesh.get_indexed_arrays();
+        let vertex_array_buffer = draw_gl::VertexArrayBuffer::new();
+        vertex_array_buffer.buffer_data_f32(&arrays.vertices, gl::STATIC_DRAW);
+        let mut element_array_buffer = draw_gl::ElementArrayBuffer::new();
+        element_array_buffer
+            .buffer_data_u32(&arrays.indices, gl::STATIC_DRAW)
+            .unwrap();
+        batches.push((vertex_array_buffer, element_array_buffer, arrays.ranges));
+    } else {
+        for arrays in mesh.get_indexed_batches(u16::MAX as usize) {
+            let vertex_array_buffer = draw_gl::VertexArrayBuffer::new();
+            vertex_array_buffer.buffer_data_f32(&arrays.vertices, gl::STATIC_DRAW);
+            let mut element_array_buffer = draw_gl::ElementArrayBuffer::new();
+            element_array_buffer.buffer_data_u16(&arrays.indices, gl::STATIC_DRAW);
+            batches.push((vertex_array_buffer, element_array_buffer, arrays.ranges));
+        }
+    }
 
-    // 頂点バッファ転送
-    let vertex_array_buffer = draw_gl::VertexArrayBuffer::new();
-    vertex_array_buffer.buffer_data_f32(&mesh.get_vertex_array(), gl::STATIC_DRAW);
-    vertex_array_buffer.vertex_attrib_pointer(attrib_position, 3, gl::FLOAT, 32, 0);
-    vertex_array_buffer.vertex_attrib_pointer(attrib_normal, 3, gl::FLOAT, 32, 12);
-    vertex_array_buffer.vertex_attrib_pointer(attrib_texcoord, 2, gl::FLOAT, 32, 24);
-
-    // テクスチャロード
-    let face_info = mesh.get_surface_info();
+    // テクスチャロード(ディレクトリ違いの同名ファイルを区別するため解決したパスで登録)
+    //  パス解決はここで1回だけ行い、描画時はマテリアル番号から登録名を引く
     let mut textures = draw_gl::Texturs::new();
-    for (_, material_index) in &face_info {
-        let material = mesh.get_matrial(*material_index);
-        if !material.diffuse_filename.is_empty() {
-            textures.load_file(&material.diffuse_filename);
+    let mut texture_keys: HashMap<i32, String> = HashMap::new();
+    for range in batches.iter().flat_map(|(_, _, ranges)| ranges) {
+        let material = mesh.get_matrial(range.material_index);
+        if !material.diffuse_map.filename.is_empty()
+            && !texture_keys.contains_key(&range.material_index)
+        {
+            let path = mesh.resolve_texture_path(material, &material.diffuse_map.filename);
+            let key = path.to_string_lossy().into_owned();
+            textures.load_file_as(&key, &path);
+            texture_keys.insert(range.material_index, key);
         }
     }
 
@@ -116,14 +151,14 @@ fn main() {
             let model_matrix = Matrix4::identity();
             let view_matrix = Matrix4::look_at_rh(
                 Point3 {
-                    x: look_direction.sin() * mesh_scale,
-                    y: 1.0,
-                    z: look_direction.cos() * mesh_scale,
+                    x: look_center.x + look_direction.sin() * camera_distance,
+                    y: look_center.y,
+                    z: look_center.z + look_direction.cos() * camera_distance,
                 },
                 Point3 {
-                    x: 0.0,
-                    y: 1.0,
-                    z: 0.0,
+                    x: look_center.x,
+                    y: look_center.y,
+                    z: look_center.z,
                 },
                 Vector3 {
                     x: 0.0,
@@ -133,10 +168,10 @@ fn main() {
             );
 
             let projection_matrix: Matrix4 = perspective(
-                cgmath::Deg(45.0f32),
+                cgmath::Deg(field_of_view),
                 window_width as f32 / window_height as f32,
-                0.1 * mesh_scale,
-                100.0 * mesh_scale,
+                clip_near,
+                clip_far,
             );
 
             // シェーダー設定
@@ -146,38 +181,54 @@ fn main() {
             gl::UniformMatrix4fv(uniform_projection, 1, gl::FALSE, projection_matrix.as_ptr());
             gl::Uniform1i(uniform_texture_sampler, 0);
 
-            //            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
-            vertex_array_buffer.bind_buffer();
-
-            let mut array_index: i32 = 0;
-            for (array_size, material_index) in &face_info {
-                let material = mesh.get_matrial(*material_index);
-
-                let texture_enable = !&material.diffuse_filename.is_empty();
-                gl::Uniform1i(uniform_texture_enable, if texture_enable { 1 } else { 0 });
-
-                if texture_enable {
-                    // テクスチャがあればバインド
-                    gl::ActiveTexture(gl::TEXTURE0);
-                    textures.get(&material.diffuse_filename).bind_texture();
-                    gl::TexParameteri(gl::TEXTURE_2D, gl::AUTO_GENERATE_MIPMAP, gl::TRUE as GLint);
-                    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
-                    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
-                    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as GLint);
-                    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as GLint);
+            for (vertex_array_buffer, element_array_buffer, ranges) in &batches {
+                // 頂点属性はバッチ毎に設定し直す
+                vertex_array_buffer.vertex_attrib_pointer(attrib_position, 3, gl::FLOAT, 32, 0);
+                vertex_array_buffer.vertex_attrib_pointer(attrib_normal, 3, gl::FLOAT, 32, 12);
+                vertex_array_buffer.vertex_attrib_pointer(attrib_texcoord, 2, gl::FLOAT, 32, 24);
+
+                // マテリアル毎に描画
+                for range in ranges {
+                    let material = mesh.get_matrial(range.material_index);
+
+                    let texture_key = texture_keys.get(&range.material_index);
+                    let texture_enable = texture_key.is_some();
+                    gl::Uniform1i(uniform_texture_enable, if texture_enable { 1 } else { 0 });
+
+                    if let Some(texture_key) = texture_key {
+                        // テクスチャがあればバインド
+                        gl::ActiveTexture(gl::TEXTURE0);
+                        textures.get(texture_key).bind_texture();
+                        gl::TexParameteri(
+                            gl::TEXTURE_2D,
+                            gl::AUTO_GENERATE_MIPMAP,
+                            gl::TRUE as GLint,
+                        );
+                        gl::TexParameteri(
+                            gl::TEXTURE_2D,
+                            gl::TEXTURE_MAG_FILTER,
+                            gl::LINEAR as GLint,
+                        );
+                        gl::TexParameteri(
+                            gl::TEXTURE_2D,
+                            gl::TEXTURE_MIN_FILTER,
+                            gl::LINEAR as GLint,
+                        );
+                        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as GLint);
+                        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as GLint);
+                    }
+
+                    // 色設定
+                    let color = Vector3 {
+                        x: material.diffuse.x,
+                        y: material.diffuse.y,
+                        z: material.diffuse.z,
+                    };
+                    gl::Uniform3fv(uniform_color, 1, color.as_ptr());
+
+                    // 描画
+                    element_array_buffer.draw_elements(gl::TRIANGLES, range.start, range.count);
                 }
-
-                // 色設定
-                let color = Vector3 {
-                    x: material.diffuse.x,
-                    y: material.diffuse.y,
-                    z: material.diffuse.z,
-                };
-                gl::Uniform3fv(uniform_color, 1, color.as_ptr());
-
-                // 描画
-                gl::DrawArrays(gl::TRIANGLES, array_index, (array_size * 3) as i32);
-                array_index += array_size;
             }
 
             // バッファスワップ
@@ -188,7 +239,6 @@ fn main() {
     }
 }
 
-
 // バーテックスシェーダー
 const VERTEX_SHADER_CODE: &str = r#"
 #version 100 
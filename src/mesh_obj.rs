@@ -1,24 +1,143 @@
 use num_traits::{Float, FromPrimitive, ToPrimitive};
 use std::fs::File;
+use std::io;
 use std::io::prelude::*;
 use std::io::BufReader;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
+mod bounds;
+mod cleanup;
+mod error;
+mod indexed;
+mod normals;
+mod tangent;
+mod texture_map;
+mod tokenizer;
+mod transform;
+mod triangulate;
+mod validate;
+mod view;
+mod writer;
+
+#[allow(unused_imports)]
+pub use writer::write_mtl;
+
+#[allow(unused_imports)]
+pub use bounds::{BoundingBox, BoundingSphere};
+#[allow(unused_imports)]
+pub use cleanup::{CleanupOptions, CleanupReport};
+pub use error::{MeshError, Warning, WarningKind};
+#[allow(unused_imports)]
+pub use indexed::{DrawRange, IndexedArrays};
+pub use texture_map::TextureMap;
+use tokenizer::{Token, Tokenizer};
+#[allow(unused_imports)]
+pub use transform::Matrix;
+#[allow(unused_imports)]
+pub use validate::{FaceLocation, IndexError, IndexKind, ValidationReport};
+#[allow(unused_imports)]
+pub use view::{FaceView, GroupView, ObjectView, PointView, SurfaceView};
+
+// リーダーから読み込んだ場合のエラー表示用の名前
+const READER_NAME: &str = "<reader>";
+
 // 即値生成
 fn get<T: FromPrimitive>(x: f64) -> T {
     T::from_f64(x).unwrap()
 }
 
-// 文字列パース
-fn parse_number<T: FromStr>(stri: &str, line_num: i32) -> Result<T, String> {
-    match stri.parse() {
-        Ok(result) => Ok(result),
-        Err(_) => Err(format!("line[{}]: parse error", line_num).to_string()),
+// 行情報(エラー位置の特定用)
+struct LineContext<'a> {
+    path: &'a str,
+    line: usize,
+    tokens: &'a [Token],
+}
+
+impl<'a> LineContext<'a> {
+    // トークン(またはその一部)の行/桁位置
+    fn position(&self, token: &str) -> (usize, usize) {
+        let pos = token.as_ptr() as usize;
+        for t in self.tokens {
+            let start = t.text.as_ptr() as usize;
+            if pos >= start && pos <= start + t.text.len() {
+                let offset = t.text[..pos - start].chars().count();
+                return (t.line, t.column + offset);
+            }
+        }
+        (self.line, 1)
+    }
+
+    fn syntax_error(&self, token: &str) -> MeshError {
+        let (line, column) = self.position(token);
+        MeshError::Syntax {
+            path: self.path.to_string(),
+            line,
+            column,
+            token: token.to_string(),
+        }
+    }
+
+    fn unknown_directive(&self, token: &str) -> MeshError {
+        let (line, column) = self.position(token);
+        MeshError::UnknownDirective {
+            path: self.path.to_string(),
+            line,
+            column,
+            token: token.to_string(),
+        }
+    }
+
+    fn invalid_index(&self, token: &str) -> MeshError {
+        let (line, column) = self.position(token);
+        MeshError::InvalidIndex {
+            path: self.path.to_string(),
+            line,
+            column,
+            token: token.to_string(),
+        }
+    }
+
+    // 文字列パース
+    fn parse_number<T: FromStr>(&self, token: &str) -> Result<T, MeshError> {
+        token.parse().map_err(|_| self.syntax_error(token))
+    }
+
+    // 数値の並びをパース(個数が counts のどれでもなければエラー)
+    fn parse_numbers<T: FromStr>(
+        &self,
+        command: &str,
+        args: &[&str],
+        counts: &[usize],
+    ) -> Result<Vec<T>, MeshError> {
+        if !counts.contains(&args.len()) {
+            return Err(self.syntax_error(command));
+        }
+        args.iter().map(|arg| self.parse_number::<T>(arg)).collect()
+    }
+
+    // 面のインデックスパース(負値は現在の要素数からの相対指定、空欄は -1)
+    //  まだ定義されていない要素(前方参照)や範囲外は InvalidIndex
+    fn parse_index(&self, token: &str, count: usize) -> Result<i32, MeshError> {
+        if token.is_empty() {
+            return Ok(-1);
+        }
+        let index = self.parse_number::<i32>(token)?;
+        let resolved = if index > 0 {
+            index - 1
+        } else {
+            count as i32 + index
+        };
+        if index == 0 || resolved < 0 || resolved as usize >= count {
+            return Err(self.invalid_index(token));
+        }
+        Ok(resolved)
     }
 }
 
 // 3D座標
 #[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Vecter3D<T: FromPrimitive> {
     pub x: T,
     pub y: T,
@@ -36,8 +155,73 @@ impl<T: FromPrimitive> Vecter3D<T> {
     }
 }
 
+// ベクトル演算
+#[allow(dead_code)]
+impl<T: Float + FromPrimitive> Vecter3D<T> {
+    fn add(&self, v: &Self) -> Self {
+        Vecter3D {
+            x: self.x + v.x,
+            y: self.y + v.y,
+            z: self.z + v.z,
+        }
+    }
+
+    fn sub(&self, v: &Self) -> Self {
+        Vecter3D {
+            x: self.x - v.x,
+            y: self.y - v.y,
+            z: self.z - v.z,
+        }
+    }
+
+    fn scale(&self, s: T) -> Self {
+        Vecter3D {
+            x: self.x * s,
+            y: self.y * s,
+            z: self.z * s,
+        }
+    }
+
+    fn dot(&self, v: &Self) -> T {
+        self.x * v.x + self.y * v.y + self.z * v.z
+    }
+
+    fn cross(&self, v: &Self) -> Self {
+        Vecter3D {
+            x: self.y * v.z - self.z * v.y,
+            y: self.z * v.x - self.x * v.z,
+            z: self.x * v.y - self.y * v.x,
+        }
+    }
+
+    fn length(&self) -> T {
+        self.dot(self).sqrt()
+    }
+
+    // 正規化(長さ0ならそのまま)
+    fn normalize(&self) -> Self {
+        let len = self.length();
+        if len > T::zero() {
+            self.scale(T::one() / len)
+        } else {
+            *self
+        }
+    }
+}
+
+// 4D座標
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Vecter4D<T: FromPrimitive> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+    pub w: T,
+}
+
 // テクスチャ座標
 #[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Texture2D<T: FromPrimitive> {
     pub u: T,
     pub v: T,
@@ -55,6 +239,7 @@ impl<T: FromPrimitive> Texture2D<T> {
 
 // マテリアル情報
 #[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Material<T: FromPrimitive> {
     pub material_name: String,
     pub diffuse: Vecter3D<T>,
@@ -62,10 +247,31 @@ pub struct Material<T: FromPrimitive> {
     pub specular: Vecter3D<T>,
     pub shininess: T,
     pub alpha: T,
-    pub diffuse_filename: String,
-    pub ambient_filename: String,
-    pub specular_filename: String,
-    pub bumpmap_filename: String,
+    pub diffuse_map: TextureMap<T>,
+    pub ambient_map: TextureMap<T>,
+    pub specular_map: TextureMap<T>,
+    pub bump_map: TextureMap<T>,
+    pub emissive: Vecter3D<T>,            // Ke
+    pub optical_density: T,               // Ni
+    pub transmission_filter: Vecter3D<T>, // Tf
+    pub illumination_model: i32,          // illum
+    pub roughness: T,                     // Pr
+    pub metallic: T,                      // Pm
+    pub sheen: T,                         // Ps
+    pub clearcoat_thickness: T,           // Pc
+    pub clearcoat_roughness: T,           // Pcr
+    pub anisotropy: T,                    // aniso
+    pub anisotropy_rotation: T,           // anisor
+    pub emissive_map: TextureMap<T>,      // map_Ke
+    pub shininess_map: TextureMap<T>,     // map_Ns
+    pub alpha_map: TextureMap<T>,         // map_d
+    pub displacement_map: TextureMap<T>,  // disp
+    pub decal_map: TextureMap<T>,         // decal
+    pub normal_map: TextureMap<T>,        // norm
+    pub roughness_map: TextureMap<T>,     // map_Pr
+    pub metallic_map: TextureMap<T>,      // map_Pm
+    pub sheen_map: TextureMap<T>,         // map_Ps
+    pub directory: PathBuf,               // テクスチャ検索の基準(mtlファイルのディレクトリ)
 }
 
 #[allow(dead_code)]
@@ -78,13 +284,52 @@ impl<T: FromPrimitive> Material<T> {
             specular: Vecter3D::<T>::new(),
             shininess: get::<T>(0.0),
             alpha: get::<T>(0.0),
-            diffuse_filename: String::new(),
-            ambient_filename: String::new(),
-            specular_filename: String::new(),
-            bumpmap_filename: String::new(),
+            diffuse_map: TextureMap::new(),
+            ambient_map: TextureMap::new(),
+            specular_map: TextureMap::new(),
+            bump_map: TextureMap::new(),
+            emissive: Vecter3D::<T>::new(),
+            optical_density: get::<T>(0.0),
+            transmission_filter: Vecter3D::<T>::new(),
+            illumination_model: 0,
+            roughness: get::<T>(0.0),
+            metallic: get::<T>(0.0),
+            sheen: get::<T>(0.0),
+            clearcoat_thickness: get::<T>(0.0),
+            clearcoat_roughness: get::<T>(0.0),
+            anisotropy: get::<T>(0.0),
+            anisotropy_rotation: get::<T>(0.0),
+            emissive_map: TextureMap::new(),
+            shininess_map: TextureMap::new(),
+            alpha_map: TextureMap::new(),
+            displacement_map: TextureMap::new(),
+            decal_map: TextureMap::new(),
+            normal_map: TextureMap::new(),
+            roughness_map: TextureMap::new(),
+            metallic_map: TextureMap::new(),
+            sheen_map: TextureMap::new(),
+            directory: PathBuf::new(),
         }
     }
 
+    // マテリアル未指定/未解決の面に使う既定のマテリアル(明るいグレー)
+    fn fallback() -> Self {
+        let mut mat = Material::<T>::new();
+        mat.diffuse = Vecter3D {
+            x: get::<T>(0.8),
+            y: get::<T>(0.8),
+            z: get::<T>(0.8),
+        };
+        mat.ambient = Vecter3D {
+            x: get::<T>(0.2),
+            y: get::<T>(0.2),
+            z: get::<T>(0.2),
+        };
+        mat.alpha = get::<T>(1.0);
+        mat.illumination_model = 1;
+        mat
+    }
+
     fn empty(&self) -> bool {
         self.material_name.len() == 0
     }
@@ -96,6 +341,7 @@ struct Point {
     vertex_index: i32,
     normal_index: i32,
     texture_coordinate_index: i32,
+    tangent_index: i32,
 }
 
 #[allow(dead_code)]
@@ -105,6 +351,7 @@ impl Point {
             vertex_index: 0,
             normal_index: 0,
             texture_coordinate_index: 0,
+            tangent_index: -1,
         }
     }
 }
@@ -113,12 +360,16 @@ impl Point {
 #[allow(dead_code)]
 struct Face {
     points: Vec<Point>,
+    smoothing_group: u32, // スムージンググループ(0ならフラット)
 }
 
 #[allow(dead_code)]
 impl Face {
     fn new() -> Self {
-        Face { points: Vec::new() }
+        Face {
+            points: Vec::new(),
+            smoothing_group: 0,
+        }
     }
 
     fn empty(&self) -> bool {
@@ -130,6 +381,7 @@ impl Face {
 #[allow(dead_code)]
 struct Surface {
     material_index: i32,
+    material_name: String, // usemtl で指定された名前
     faces: Vec<Face>,
 }
 
@@ -138,6 +390,7 @@ impl Surface {
     fn new() -> Self {
         Surface {
             material_index: -1,
+            material_name: String::new(),
             faces: Vec::new(),
         }
     }
@@ -198,7 +451,12 @@ pub struct Mesh<T: FromPrimitive> {
     vertexes: Vec<Vecter3D<T>>,             // 頂点座標リスト
     texture_coordinates: Vec<Texture2D<T>>, // テクスチャ座標リスト
     normals: Vec<Vecter3D<T>>,              // 法線ベクトルリスト
+    tangents: Vec<Vecter4D<T>>,             // 接線ベクトルリスト(w は従法線の向き)
     materials: Vec<Material<T>>,            // マテリアル
+    search_dirs: Vec<PathBuf>,              // テクスチャ検索ディレクトリ
+    material_libraries: Vec<String>,        // mtllib で読み込んだファイル名
+    flip_v: bool,                           // 頂点配列の v を 1 - v に反転して出力する
+    default_material: Material<T>,          // マテリアル未指定/未解決の面用
 }
 
 #[allow(dead_code)]
@@ -211,367 +469,1026 @@ impl<T: FromPrimitive> Mesh<T> {
             vertexes: Vec::new(),
             texture_coordinates: Vec::new(),
             normals: Vec::new(),
+            tangents: Vec::new(),
             materials: Vec::new(),
+            search_dirs: Vec::new(),
+            material_libraries: Vec::new(),
+            flip_v: true,
+            default_material: Material::fallback(),
         }
     }
 
     fn empty(&self) -> bool {
         self.objects.len() == 0
     }
+
+    // マテリアル取得(未指定 -1 や範囲外なら既定のマテリアル)
+    pub fn get_matrial(&self, material_index: i32) -> &Material<T> {
+        self.find_material(material_index)
+            .unwrap_or(&self.default_material)
+    }
+
+    // マテリアル取得(未指定 -1 や範囲外なら None)
+    pub fn find_material(&self, material_index: i32) -> Option<&Material<T>> {
+        if material_index < 0 {
+            return None;
+        }
+        self.materials.get(material_index as usize)
+    }
+
+    // 名前からマテリアル番号を検索
+    pub fn find_material_index(&self, name: &str) -> Option<i32> {
+        self.materials
+            .iter()
+            .position(|mat| mat.material_name == name)
+            .map(|i| i as i32)
+    }
+
+    // 既定のマテリアル
+    pub fn get_default_material(&self) -> &Material<T> {
+        &self.default_material
+    }
+}
+
+// mtllib で参照されたマテリアルファイルを開く
+pub trait MaterialResolver {
+    fn open(&mut self, name: &str) -> io::Result<Box<dyn BufRead>>;
+
+    // 開いたファイルのディレクトリ(テクスチャパスの基準)
+    fn directory(&self, _name: &str) -> Option<PathBuf> {
+        None
+    }
 }
 
+// クロージャもリゾルバとして使えるようにする
+impl<F: FnMut(&str) -> io::Result<Box<dyn BufRead>>> MaterialResolver for F {
+    fn open(&mut self, name: &str) -> io::Result<Box<dyn BufRead>> {
+        self(name)
+    }
+}
+
+// ファイルシステムから開くリゾルバ
 #[allow(dead_code)]
-impl<T: FromStr + Float + FromPrimitive + ToPrimitive> Mesh<T> {
-    pub fn load(filename: &str) -> Result<Box<Mesh<T>>, String> {
-        // ファイルオープン
-        let f = match File::open(&filename) {
-            Err(_) => {
-                return Err(format!("couldn't open {}", filename));
-            }
-            Ok(file) => file,
+pub struct FileResolver {
+    pub base_dir: PathBuf,         // 参照元ファイルのディレクトリ
+    pub search_dirs: Vec<PathBuf>, // 追加の検索ディレクトリ
+}
+
+#[allow(dead_code)]
+impl FileResolver {
+    pub fn new(base_dir: &Path, search_dirs: &[PathBuf]) -> Self {
+        FileResolver {
+            base_dir: base_dir.to_path_buf(),
+            search_dirs: search_dirs.to_vec(),
+        }
+    }
+
+    // 基準ディレクトリ、検索ディレクトリの順に探す
+    pub fn resolve(&self, name: &str) -> Option<PathBuf> {
+        search_file(name, &self.base_dir, &self.search_dirs)
+    }
+}
+
+impl MaterialResolver for FileResolver {
+    fn open(&mut self, name: &str) -> io::Result<Box<dyn BufRead>> {
+        let path = self
+            .resolve(name)
+            .unwrap_or_else(|| self.base_dir.join(name));
+        Ok(Box::new(BufReader::new(File::open(path)?)))
+    }
+
+    fn directory(&self, name: &str) -> Option<PathBuf> {
+        let path = self.resolve(name)?;
+        Some(path.parent().map(Path::to_path_buf).unwrap_or_default())
+    }
+}
+
+// ファイル検索(見つからなければ None)
+fn search_file(name: &str, base_dir: &Path, search_dirs: &[PathBuf]) -> Option<PathBuf> {
+    let name = Path::new(name);
+    if name.is_absolute() {
+        return if name.is_file() {
+            Some(name.to_path_buf())
+        } else {
+            None
         };
+    }
+    std::iter::once(base_dir)
+        .chain(search_dirs.iter().map(PathBuf::as_path))
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+}
 
-        // オブジェクト準備
-        let mut mesh = Box::new(Mesh::<T>::new());
-        let mut obj = Object::new();
-        let mut grp = Group::new();
-        let mut surf = Surface::new();
+// ファイルの上方向の軸
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpAxis {
+    Y, // Y-up(そのまま)
+    Z, // Z-up(CAD 等、Y-up に変換する)
+}
+
+// 読み込みオプション
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct LoadOptions {
+    pub strict: bool,              // false なら解釈できない行は警告として読み飛ばす
+    pub search_dirs: Vec<PathBuf>, // mtllib やテクスチャの追加検索ディレクトリ
+    pub generate_normals: bool,    // 法線の無い頂点に法線を生成する
+    pub recompute_normals: bool,   // ファイルの法線を捨てて全て再計算する
+    pub up_axis: UpAxis,           // ファイルの上方向の軸
+    pub flip_handedness: bool,     // Z を反転して右手系/左手系を入れ替える(面の向きも反転)
+    pub flip_v: bool,              // 頂点配列の v を 1 - v に反転する(ファイルの値はそのまま保持)
+    pub unit_scale: f64,           // 座標の倍率(単位変換用)
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        LoadOptions {
+            strict: true,
+            search_dirs: Vec::new(),
+            generate_normals: true,
+            recompute_normals: false,
+            up_axis: UpAxis::Y,
+            flip_handedness: false,
+            flip_v: true,
+            unit_scale: 1.0,
+        }
+    }
+}
+
+// 読み込みエラーの変換(不正な UTF-8 は書式エラー、それ以外は I/O エラー)
+fn read_error(filename: &str, line: usize, error: io::Error) -> MeshError {
+    if error.kind() == io::ErrorKind::InvalidData {
+        MeshError::Syntax {
+            path: filename.to_string(),
+            line,
+            column: 1,
+            token: error.to_string(),
+        }
+    } else {
+        MeshError::Io {
+            path: filename.to_string(),
+            source: error,
+        }
+    }
+}
+
+// 読み込み途中の状態
+struct ObjLoader<'a, T: FromPrimitive> {
+    options: &'a LoadOptions,
+    resolver: &'a mut dyn MaterialResolver,
+    mesh: Box<Mesh<T>>,
+    obj: Object,
+    grp: Group,
+    surf: Surface,
+    smoothing_group: u32,
+    warnings: Vec<Warning>,
+}
 
-        let mut line_num: i32 = 0;
+#[allow(dead_code)]
+impl<'a, T: FromStr + Float + FromPrimitive + ToPrimitive> ObjLoader<'a, T> {
+    fn new(options: &'a LoadOptions, resolver: &'a mut dyn MaterialResolver) -> Self {
+        let mut mesh = Box::new(Mesh::<T>::new());
+        mesh.search_dirs = options.search_dirs.clone();
+        mesh.flip_v = options.flip_v;
+        ObjLoader {
+            options,
+            resolver,
+            mesh,
+            obj: Object::new(),
+            grp: Group::new(),
+            surf: Surface::new(),
+            smoothing_group: 0,
+            warnings: Vec::new(),
+        }
+    }
 
-        // 行単位で処理
-        let reader = BufReader::new(f);
-        for line in reader.lines() {
-            line_num += 1;
+    // エラーを strict なら返し、そうでなければ警告として記録
+    fn recover(&mut self, error: MeshError) -> Result<(), MeshError> {
+        if self.options.strict {
+            return Err(error);
+        }
+        let kind = match &error {
+            MeshError::Io { .. } => return Err(error),
+            MeshError::Syntax { .. } => WarningKind::Syntax,
+            MeshError::UnknownDirective { .. } => WarningKind::UnknownDirective,
+            MeshError::InvalidIndex { .. } => WarningKind::InvalidIndex,
+        };
+        self.warnings.push(Warning {
+            kind,
+            path: error.path().to_string(),
+            line: error.line().unwrap_or(0),
+            column: error.column().unwrap_or(0),
+            token: error.token().unwrap_or("").to_string(),
+        });
+        Ok(())
+    }
 
-            // 空白で分解
-            let text: &str = &line.unwrap();
-            let params: Vec<&str> = text.trim().split(" ").collect();
+    fn read<R: BufRead>(&mut self, reader: R, filename: &str) -> Result<(), MeshError> {
+        // 論理行単位で処理
+        let mut tokenizer = Tokenizer::new(reader);
+        while let Some(line) = tokenizer.next() {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => {
+                    self.recover(read_error(filename, tokenizer.line_num(), e))?;
+                    continue;
+                }
+            };
 
             // 空行 or コメント行ならスキップ
-            if params.len() <= 0 || params[0].len() <= 0 || params[0].chars().nth(0).unwrap() == '#'
-            {
+            if line.tokens.is_empty() {
                 continue;
             }
 
             // コマンドと引数に分解
+            let params: Vec<&str> = line.tokens.iter().map(|t| t.text.as_str()).collect();
             let command: &str = params[0];
             let args: &[&str] = &params[1..];
+            let ctx = LineContext {
+                path: filename,
+                line: line.line,
+                tokens: &line.tokens,
+            };
+
+            if let Err(e) = self.parse_line(&ctx, command, args) {
+                self.recover(e)?;
+            }
+        }
 
-            match (command, args.len()) {
-                ("o", 1) => {
-                    // オブジェクト
-                    if !obj.empty() {
-                        mesh.objects.push(obj);
-                        obj = Object::new();
-                    }
-                    obj.object_name = args[0].to_string();
-                }
-
-                ("g", 1) => {
-                    // グループ
-                    if !surf.empty() {
-                        grp.surfaces.push(surf);
-                        surf = Surface::new();
-                    }
-                    if !grp.empty() {
-                        obj.groups.push(grp);
-                    }
+        Ok(())
+    }
 
-                    grp = Group::new();
-                    grp.group_name = args[0].to_string();
+    fn parse_line(
+        &mut self,
+        ctx: &LineContext,
+        command: &str,
+        args: &[&str],
+    ) -> Result<(), MeshError> {
+        match (command, args.len()) {
+            ("o", 1) => {
+                // オブジェクト
+                self.flush_group();
+                if !self.obj.empty() {
+                    self.mesh
+                        .objects
+                        .push(std::mem::replace(&mut self.obj, Object::new()));
                 }
+                self.obj.object_name = args[0].to_string();
+            }
 
-                ("mtllib", 1) => {
-                    // マテリアルファイル読み込み＆登録
-                    Mesh::load_mtl(&mut mesh, args[0])?;
-                }
+            ("g", 1) => {
+                // グループ
+                self.flush_group();
+                self.grp.group_name = args[0].to_string();
+            }
 
-                ("usemtl", 1) => {
-                    // サーフェース登録
-                    if !surf.empty() {
-                        grp.surfaces.push(surf);
+            ("mtllib", n) if n >= 1 => {
+                // マテリアルファイル読み込み＆登録(複数指定可)
+                let mut i = 0;
+                while i < n {
+                    // 空白を含むファイル名は開けるまで後続の引数と連結してみる
+                    //  開けなければ最初(引数1つ)のエラーを報告する
+                    let mut opened = None;
+                    let mut first_error = None;
+                    for j in i + 1..=n {
+                        let name = args[i..j].join(" ");
+                        match self.resolver.open(&name) {
+                            Ok(reader) => {
+                                opened = Some((j, name, reader));
+                                break;
+                            }
+                            Err(e) => {
+                                first_error.get_or_insert(e);
+                            }
+                        }
                     }
-                    surf = Surface::new();
-
-                    // 利用マテリアル検索
-                    for (i, mat) in mesh.materials.iter().enumerate() {
-                        if mat.material_name == args[0] {
-                            surf.material_index = i as i32;
-                            break;
+                    match opened {
+                        Some((j, name, reader)) => {
+                            let directory = self.resolver.directory(&name).unwrap_or_default();
+                            self.read_mtl(reader, &name, &directory)?;
+                            self.mesh.material_libraries.push(name);
+                            i = j;
+                        }
+                        None => {
+                            let error = first_error
+                                .unwrap_or_else(|| io::Error::from(io::ErrorKind::NotFound));
+                            if self.options.strict {
+                                return Err(MeshError::Io {
+                                    path: args[i].to_string(),
+                                    source: error,
+                                });
+                            }
+                            let (line, column) = ctx.position(args[i]);
+                            self.warnings.push(Warning {
+                                kind: WarningKind::MissingFile,
+                                path: ctx.path.to_string(),
+                                line,
+                                column,
+                                token: args[i].to_string(),
+                            });
+                            i += 1;
                         }
                     }
                 }
+            }
 
-                ("v", 3) => {
-                    // 頂点情報
-                    mesh.vertexes.push(Vecter3D::<T> {
-                        x: parse_number(args[0], line_num)?,
-                        y: parse_number(args[1], line_num)?,
-                        z: parse_number(args[2], line_num)?,
-                    })
+            ("usemtl", 1) => {
+                // サーフェース登録
+                if !self.surf.empty() {
+                    self.grp
+                        .surfaces
+                        .push(std::mem::replace(&mut self.surf, Surface::new()));
                 }
-
-                ("vt", 2) => {
-                    // テクスチャ座標
-                    mesh.texture_coordinates.push(Texture2D::<T> {
-                        u: parse_number(args[0], line_num)?,
-                        v: get::<T>(1.0) - parse_number(args[1], line_num)?, // 左下原点(OpenGL座標)に変換
-                    })
+                self.surf = Surface::new();
+                self.surf.material_name = args[0].to_string();
+
+                // 利用マテリアル検索(見つからなければ既定のマテリアルで描画)
+                match self.mesh.find_material_index(args[0]) {
+                    Some(i) => self.surf.material_index = i,
+                    None => {
+                        let (line, column) = ctx.position(args[0]);
+                        self.warnings.push(Warning {
+                            kind: WarningKind::UnresolvedMaterial,
+                            path: ctx.path.to_string(),
+                            line,
+                            column,
+                            token: args[0].to_string(),
+                        });
+                    }
                 }
+            }
 
-                ("vn", 3) => {
-                    // 法線情報
-                    mesh.normals.push(Vecter3D::<T> {
-                        x: parse_number(args[0], line_num)?,
-                        y: parse_number(args[1], line_num)?,
-                        z: parse_number(args[2], line_num)?,
-                    })
-                }
+            // v/vt/vn は解釈できなくても後続の番号がずれないよう仮の値(0)を登録する
+            ("v", _) => {
+                // 頂点情報(x y z [w] / 頂点カラー付き x y z r g b [a]、w と色は読み捨て)
+                let values = ctx.parse_numbers::<T>(command, args, &[3, 4, 6, 7]);
+                let vertex = match &values {
+                    Ok(values) => Vecter3D::<T> {
+                        x: values[0],
+                        y: values[1],
+                        z: values[2],
+                    },
+                    Err(_) => Vecter3D::<T>::new(),
+                };
+                self.mesh.vertexes.push(vertex);
+                values?;
+            }
 
-                ("f", n) => {
-                    // 面
-                    if n < 3 {
-                        return Err(format!("[{}: There are too few points", line_num).to_string());
-                    }
+            ("vt", _) => {
+                // テクスチャ座標(u [v [w]]、w は読み捨て)
+                let values = ctx.parse_numbers::<T>(command, args, &[1, 2, 3]);
+                let texture_coordinate = match &values {
+                    Ok(values) => Texture2D::<T> {
+                        u: values[0],
+                        v: values.get(1).copied().unwrap_or_else(T::zero),
+                    },
+                    Err(_) => Texture2D::<T>::new(),
+                };
+                self.mesh.texture_coordinates.push(texture_coordinate);
+                values?;
+            }
 
-                    let mut face = Face::new();
-                    for arg in args {
-                        let indexes: Vec<&str> = arg.split("/").collect();
-                        face.points.push(Point {
-                            vertex_index: if indexes.len() > 0 {
-                                parse_number::<i32>(indexes[0], line_num)? - 1
-                            } else {
-                                -1
-                            },
-                            texture_coordinate_index: if indexes.len() > 1 {
-                                parse_number::<i32>(indexes[1], line_num)? - 1
-                            } else {
-                                -1
-                            },
-                            normal_index: if indexes.len() > 2 {
-                                parse_number::<i32>(indexes[2], line_num)? - 1
-                            } else {
-                                -1
-                            },
-                        });
-                    }
-                    surf.faces.push(face);
-                }
+            ("vn", _) => {
+                // 法線情報
+                let values = ctx.parse_numbers::<T>(command, args, &[3]);
+                let normal = match &values {
+                    Ok(values) => Vecter3D::<T> {
+                        x: values[0],
+                        y: values[1],
+                        z: values[2],
+                    },
+                    Err(_) => Vecter3D::<T>::new(),
+                };
+                self.mesh.normals.push(normal);
+                values?;
+            }
 
-                ("s", 1) => { // スムーズシェーディングON/OFF
+            ("f", n) => {
+                // 面
+                if n < 3 {
+                    return Err(ctx.syntax_error(command));
                 }
 
-                _ => {
-                    // エラー
-                    //                  println!("{}[{}]: format error: {}", filename, line_num, text);
-                    return Err(format!(
-                        "{}[{}]:Format error\n{}\n",
-                        filename, line_num, text
-                    ));
+                let mut face = Face::new();
+                face.smoothing_group = self.smoothing_group;
+                for arg in args {
+                    // v, v/vt, v//vn, v/vt/vn の各形式
+                    let indexes: Vec<&str> = arg.split('/').collect();
+                    if indexes.len() > 3 || indexes[0].is_empty() {
+                        return Err(ctx.syntax_error(arg));
+                    }
+                    let slot = |i: usize| indexes.get(i).copied().unwrap_or("");
+                    face.points.push(Point {
+                        vertex_index: ctx.parse_index(slot(0), self.mesh.vertexes.len())?,
+                        texture_coordinate_index: ctx
+                            .parse_index(slot(1), self.mesh.texture_coordinates.len())?,
+                        normal_index: ctx.parse_index(slot(2), self.mesh.normals.len())?,
+                        tangent_index: -1,
+                    });
                 }
+                self.surf.faces.push(face);
+            }
+
+            ("s", 1) => {
+                // スムージンググループ(off/0 で無効)
+                self.smoothing_group = match args[0] {
+                    "off" => 0,
+                    _ => ctx.parse_number::<u32>(args[0])?,
+                };
+            }
+
+            _ => {
+                // エラー
+                return Err(ctx.unknown_directive(command));
             }
         }
+        Ok(())
+    }
 
-        if !surf.empty() {
-            grp.surfaces.push(surf);
+    // 作成中のグループを確定
+    fn flush_group(&mut self) {
+        if !self.surf.empty() {
+            // マテリアル指定は次のグループに引き継ぐ
+            let mut surf = Surface::new();
+            surf.material_index = self.surf.material_index;
+            surf.material_name = self.surf.material_name.clone();
+            self.grp
+                .surfaces
+                .push(std::mem::replace(&mut self.surf, surf));
+        }
+        if !self.grp.empty() {
+            self.obj
+                .groups
+                .push(std::mem::replace(&mut self.grp, Group::new()));
+        }
+        self.grp = Group::new();
+    }
+
+    fn finish(mut self) -> (Box<Mesh<T>>, Vec<Warning>) {
+        self.flush_group();
+        if !self.obj.empty() {
+            self.mesh.objects.push(self.obj);
         }
 
-        if !grp.empty() {
-            obj.groups.push(grp);
+        // 座標系の変換(Z-up → Y-up、右手系/左手系、単位)
+        if self.options.up_axis == UpAxis::Z {
+            let (o, l) = (T::zero(), T::one());
+            self.mesh
+                .transform(&[[l, o, o, o], [o, o, -l, o], [o, l, o, o], [o, o, o, l]]);
+        }
+        let scale = get::<T>(self.options.unit_scale);
+        let scale_z = if self.options.flip_handedness {
+            -scale
+        } else {
+            scale
+        };
+        if scale != T::one() || scale_z != T::one() {
+            self.mesh.scale(scale, scale, scale_z);
         }
 
-        if !obj.empty() {
-            mesh.objects.push(obj);
+        // 法線生成
+        if self.options.recompute_normals {
+            self.mesh.generate_normals(true);
+        } else if self.options.generate_normals {
+            self.mesh.generate_normals(false);
         }
 
-        return Ok(mesh);
+        (self.mesh, self.warnings)
     }
 
     // マテリアル読み込み
-    fn load_mtl(mesh: &mut Mesh<T>, filename: &str) -> Result<bool, String> {
-        // ファイルオープン
-        let f = match File::open(&filename) {
-            Err(_) => {
-                return Err(format!("couldn't open {}", filename).to_string());
-            }
-            Ok(file) => file,
-        };
-
+    fn read_mtl<R: BufRead>(
+        &mut self,
+        reader: R,
+        filename: &str,
+        directory: &Path,
+    ) -> Result<(), MeshError> {
         // オブジェクト準備
         let mut mat = Material::<T>::new();
-
-        // 行単位で読み込み
-        let mut line_num: i32 = 0;
-        let reader = BufReader::new(f);
-        for line in reader.lines() {
-            line_num += 1;
-
-            // 空白で分解
-            let text: &str = &line.unwrap();
-            let params: Vec<&str> = text.trim().split(" ").collect();
+        mat.directory = directory.to_path_buf();
+
+        // 論理行単位で読み込み
+        let mut tokenizer = Tokenizer::new(reader);
+        while let Some(line) = tokenizer.next() {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => {
+                    self.recover(read_error(filename, tokenizer.line_num(), e))?;
+                    continue;
+                }
+            };
 
             // 空行 or コメント行ならスキップ
-            if params.len() <= 0 || params[0].len() <= 0 || params[0].chars().nth(0).unwrap() == '#'
-            {
+            if line.tokens.is_empty() {
                 continue;
             }
 
             // コマンドと引数に分解
+            let params: Vec<&str> = line.tokens.iter().map(|t| t.text.as_str()).collect();
             let command: &str = params[0];
             let args: &[&str] = &params[1..];
+            let ctx = LineContext {
+                path: filename,
+                line: line.line,
+                tokens: &line.tokens,
+            };
+
+            if let Err(e) = self.parse_mtl_line(&mut mat, &ctx, command, args) {
+                self.recover(e)?;
+            }
+        }
 
-            match (command, args.len()) {
-                ("newmtl", 1) => {
-                    if !mat.empty() {
-                        mesh.materials.push(mat);
-                        mat = Material::<T>::new();
-                    }
-                    mat.material_name = args[0].to_string();
-                }
+        if !mat.empty() {
+            self.mesh.materials.push(mat);
+        }
 
-                ("Ka", 3) => {
-                    mat.ambient.x = parse_number::<T>(args[0], line_num)?;
-                    mat.ambient.y = parse_number::<T>(args[1], line_num)?;
-                    mat.ambient.z = parse_number::<T>(args[2], line_num)?;
-                }
+        Ok(())
+    }
 
-                ("Kd", 3) => {
-                    mat.diffuse.x = parse_number::<T>(args[0], line_num)?;
-                    mat.diffuse.y = parse_number::<T>(args[1], line_num)?;
-                    mat.diffuse.z = parse_number::<T>(args[2], line_num)?;
+    fn parse_mtl_line(
+        &mut self,
+        mat: &mut Material<T>,
+        ctx: &LineContext,
+        command: &str,
+        args: &[&str],
+    ) -> Result<(), MeshError> {
+        // map_* は大文字小文字を区別しない(map_Kd / map_kd / map_KD 等)
+        let is_map = command
+            .get(..4)
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case("map_"));
+        let keyword = if is_map {
+            command.to_ascii_lowercase()
+        } else {
+            command.to_string()
+        };
+        match (keyword.as_str(), args.len()) {
+            ("newmtl", 1) => {
+                if !mat.empty() {
+                    let directory = mat.directory.clone();
+                    self.mesh
+                        .materials
+                        .push(std::mem::replace(mat, Material::<T>::new()));
+                    mat.directory = directory;
                 }
+                mat.material_name = args[0].to_string();
+            }
 
-                ("Ks", 3) => {
-                    mat.specular.x = parse_number::<T>(args[0], line_num)?;
-                    mat.specular.y = parse_number::<T>(args[1], line_num)?;
-                    mat.specular.z = parse_number::<T>(args[2], line_num)?;
-                }
+            ("Ka", 3) => {
+                mat.ambient.x = ctx.parse_number::<T>(args[0])?;
+                mat.ambient.y = ctx.parse_number::<T>(args[1])?;
+                mat.ambient.z = ctx.parse_number::<T>(args[2])?;
+            }
 
-                ("Ke", 3) => {}
+            ("Kd", 3) => {
+                mat.diffuse.x = ctx.parse_number::<T>(args[0])?;
+                mat.diffuse.y = ctx.parse_number::<T>(args[1])?;
+                mat.diffuse.z = ctx.parse_number::<T>(args[2])?;
+            }
 
-                ("Ns", 1) => {
-                    mat.shininess = parse_number::<T>(args[0], line_num)?;
-                }
+            ("Ks", 3) => {
+                mat.specular.x = ctx.parse_number::<T>(args[0])?;
+                mat.specular.y = ctx.parse_number::<T>(args[1])?;
+                mat.specular.z = ctx.parse_number::<T>(args[2])?;
+            }
 
-                ("d", 1) | ("Tr", 1) => {
-                    mat.alpha = parse_number::<T>(args[0], line_num)?;
-                }
+            ("Ke", 3) => {
+                mat.emissive.x = ctx.parse_number::<T>(args[0])?;
+                mat.emissive.y = ctx.parse_number::<T>(args[1])?;
+                mat.emissive.z = ctx.parse_number::<T>(args[2])?;
+            }
 
-                ("map_Ka", 1) => {
-                    mat.ambient_filename = args[0].to_string();
-                }
+            ("Ns", 1) => {
+                mat.shininess = ctx.parse_number::<T>(args[0])?;
+            }
 
-                ("map_Kd", 1) => {
-                    mat.diffuse_filename = args[0].to_string();
-                }
+            ("d", 1) => {
+                mat.alpha = ctx.parse_number::<T>(args[0])?;
+            }
 
-                ("map_Ks", 1) => {
-                    mat.specular_filename = args[0].to_string();
-                }
+            ("Tr", 1) => {
+                // 透明度(= 1 - d)
+                mat.alpha = get::<T>(1.0) - ctx.parse_number::<T>(args[0])?;
+            }
 
-                ("map_Ns", 1) => {}
+            ("map_ka", n) if n >= 1 => {
+                mat.ambient_map = TextureMap::parse(ctx, args)?;
+            }
 
-                ("map_d", 1) => {} // lpha texture map
+            ("map_kd", n) if n >= 1 => {
+                mat.diffuse_map = TextureMap::parse(ctx, args)?;
+            }
 
-                ("illum", 1) => {}
+            ("map_ks", n) if n >= 1 => {
+                mat.specular_map = TextureMap::parse(ctx, args)?;
+            }
 
-                ("map_bump", 1) | ("bump", 1) => {
-                    mat.bumpmap_filename = args[0].to_string();
-                }
+            ("map_bump", n) | ("bump", n) if n >= 1 => {
+                mat.bump_map = TextureMap::parse(ctx, args)?;
+            }
+
+            ("Ni", 1) => {
+                // optical density
+                mat.optical_density = ctx.parse_number::<T>(args[0])?;
+            }
 
-                ("disp", 1) => {}   // displacement map
-                ("decal ", 1) => {} // stencil decal texture
-
-                ("Ni", _) => {} //optical density
-                ("Tf", _) => {} // Transmission Filter Color
-
-                ("Pr", 1) | ("map_Pr", 1) => {} // roughness
-                ("Pm", 1) | ("map_Pm", 1) => {} // metallic
-                ("Ps", 1) | ("map_Ps", 1) => {} // sheen
-                ("Pc", _) => {}                 // clearcoat thickness
-                ("Pcr", _) => {}                // clearcoat roughness
-                ("map_ke", 1) => {}             // emissive
-                ("aniso", 1) => {}              // anisotropy
-                ("anisor", 1) => {}             // anisotropy rotation
-                ("norm", 1) => {}               // normal map
-
-                _ => {
-                    // エラー
-                    //                  println!("{}[{}]: format error: {}", filename, line_num, text);
-                    return Err(format!(
-                        "{}[{}]:Format error\n{}\n",
-                        filename, line_num, text
-                    ));
+            ("Tf", _) => {
+                // Transmission Filter Color (Tf r [g b] / Tf xyz x [y z])
+                let values = match args.first() {
+                    Some(&"xyz") => &args[1..],
+                    _ => args,
+                };
+                match values.len() {
+                    1 => {
+                        let value = ctx.parse_number::<T>(values[0])?;
+                        mat.transmission_filter = Vecter3D {
+                            x: value,
+                            y: value,
+                            z: value,
+                        };
+                    }
+                    3 => {
+                        mat.transmission_filter.x = ctx.parse_number::<T>(values[0])?;
+                        mat.transmission_filter.y = ctx.parse_number::<T>(values[1])?;
+                        mat.transmission_filter.z = ctx.parse_number::<T>(values[2])?;
+                    }
+                    _ => return Err(ctx.unknown_directive(command)),
                 }
             }
-        }
 
-        if !mat.empty() {
-            mesh.materials.push(mat);
+            ("illum", 1) => {
+                mat.illumination_model = ctx.parse_number::<i32>(args[0])?;
+            }
+
+            ("Pr", 1) => mat.roughness = ctx.parse_number::<T>(args[0])?,
+            ("Pm", 1) => mat.metallic = ctx.parse_number::<T>(args[0])?,
+            ("Ps", 1) => mat.sheen = ctx.parse_number::<T>(args[0])?,
+            ("Pc", 1) => mat.clearcoat_thickness = ctx.parse_number::<T>(args[0])?,
+            ("Pcr", 1) => mat.clearcoat_roughness = ctx.parse_number::<T>(args[0])?,
+            ("aniso", 1) => mat.anisotropy = ctx.parse_number::<T>(args[0])?,
+            ("anisor", 1) => mat.anisotropy_rotation = ctx.parse_number::<T>(args[0])?,
+
+            ("map_ke", n) if n >= 1 => mat.emissive_map = TextureMap::parse(ctx, args)?,
+            ("map_ns", n) if n >= 1 => mat.shininess_map = TextureMap::parse(ctx, args)?,
+            ("map_d", n) if n >= 1 => mat.alpha_map = TextureMap::parse(ctx, args)?,
+            ("disp", n) if n >= 1 => mat.displacement_map = TextureMap::parse(ctx, args)?,
+            ("decal", n) if n >= 1 => mat.decal_map = TextureMap::parse(ctx, args)?,
+            ("norm", n) if n >= 1 => mat.normal_map = TextureMap::parse(ctx, args)?,
+            ("map_pr", n) if n >= 1 => mat.roughness_map = TextureMap::parse(ctx, args)?,
+            ("map_pm", n) if n >= 1 => mat.metallic_map = TextureMap::parse(ctx, args)?,
+            ("map_ps", n) if n >= 1 => mat.sheen_map = TextureMap::parse(ctx, args)?,
+
+            _ => {
+                // エラー
+                return Err(ctx.unknown_directive(command));
+            }
         }
+        Ok(())
+    }
+}
+
+#[allow(dead_code)]
+impl<T: FromStr + Float + FromPrimitive + ToPrimitive> Mesh<T> {
+    pub fn load(filename: &str) -> Result<Box<Mesh<T>>, MeshError> {
+        let (mesh, _) = Mesh::load_with_options(filename, &LoadOptions::default())?;
+        Ok(mesh)
+    }
+
+    // オプション指定読み込み(非strictなら読み飛ばした行を警告として返す)
+    pub fn load_with_options(
+        filename: &str,
+        options: &LoadOptions,
+    ) -> Result<(Box<Mesh<T>>, Vec<Warning>), MeshError> {
+        // ファイルオープン
+        let f = match File::open(filename) {
+            Err(e) => {
+                return Err(MeshError::Io {
+                    path: filename.to_string(),
+                    source: e,
+                });
+            }
+            Ok(file) => file,
+        };
+
+        // 相対パスは OBJ ファイルのディレクトリから解決
+        let base_dir = Path::new(filename)
+            .parent()
+            .unwrap_or_else(|| Path::new(""));
+        let mut resolver = FileResolver::new(base_dir, &options.search_dirs);
+        let mut loader = ObjLoader::<T>::new(options, &mut resolver);
+        loader.mesh.search_dirs.insert(0, base_dir.to_path_buf());
+        loader.read(BufReader::new(f), filename)?;
+        Ok(loader.finish())
+    }
+
+    // 任意のリーダーから読み込み(mtllib は resolver で開く)
+    pub fn from_reader<R: BufRead>(
+        reader: R,
+        resolver: &mut dyn MaterialResolver,
+    ) -> Result<Box<Mesh<T>>, MeshError> {
+        let (mesh, _) = Mesh::from_reader_with_options(reader, resolver, &LoadOptions::default())?;
+        Ok(mesh)
+    }
+
+    pub fn from_reader_with_options<R: BufRead>(
+        reader: R,
+        resolver: &mut dyn MaterialResolver,
+        options: &LoadOptions,
+    ) -> Result<(Box<Mesh<T>>, Vec<Warning>), MeshError> {
+        let mut loader = ObjLoader::<T>::new(options, resolver);
+        loader.read(reader, READER_NAME)?;
+        Ok(loader.finish())
+    }
 
-        return Ok(true);
+    // マテリアルファイル単体の読み込み
+    pub fn load_materials<R: BufRead>(
+        reader: R,
+        filename: &str,
+    ) -> Result<Vec<Material<T>>, MeshError> {
+        let options = LoadOptions::default();
+        let directory = Path::new(filename)
+            .parent()
+            .unwrap_or_else(|| Path::new(""));
+        let mut resolver = FileResolver::new(directory, &[]);
+        let mut loader = ObjLoader::<T>::new(&options, &mut resolver);
+        loader.read_mtl(reader, filename, directory)?;
+        Ok(loader.finish().0.materials)
     }
 
+    // 頂点配列(マテリアル毎にまとめて並べる、範囲は get_draw_ranges)
     pub fn get_vertex_array(&self) -> Vec<T> {
         let mut buffer = Vec::<T>::new();
-        for obj in &self.objects {
-            for grp in &obj.groups {
-                for surf in &grp.surfaces {
-                    for face in &surf.faces {
-                        for i in 0..3 {
-                            assert!(face.points[i].vertex_index >= 0, "");
-                            let idx = face.points[i].vertex_index as usize;
-                            let vertex = &self.vertexes[idx];
-                            buffer.push(vertex.x);
-                            buffer.push(vertex.y);
-                            buffer.push(vertex.z);
-
-                            if face.points[i].normal_index > 0 {
-                                let idx = face.points[i].normal_index as usize;
-                                let normal = &self.normals[idx];
-                                buffer.push(normal.x);
-                                buffer.push(normal.y);
-                                buffer.push(normal.z);
-                            } else {
-                                buffer.push(get::<T>(0.0));
-                                buffer.push(get::<T>(0.0));
-                                buffer.push(get::<T>(0.0));
-                            }
-
-                            if face.points[i].texture_coordinate_index > 0 {
-                                let idx = face.points[i].texture_coordinate_index as usize;
-                                let texture_coordinate = &self.texture_coordinates[idx];
-                                buffer.push(texture_coordinate.u);
-                                buffer.push(texture_coordinate.v);
-                            } else {
-                                buffer.push(get::<T>(0.0));
-                                buffer.push(get::<T>(0.0));
-                            }
-                        }
-                    }
-                }
+        for (_, triangles) in self.material_triangles() {
+            for point in triangles.iter().flatten() {
+                self.push_point(&mut buffer, point);
             }
         }
         buffer
     }
 
+    // get_vertex_array のマテリアル毎の描画範囲(頂点単位)
+    pub fn get_draw_ranges(&self) -> Vec<DrawRange> {
+        let mut ranges = Vec::new();
+        let mut start = 0;
+        for (material_index, triangles) in self.material_triangles() {
+            let count = triangles.len() * 3;
+            ranges.push(DrawRange {
+                material_index,
+                start,
+                count,
+            });
+            start += count;
+        }
+        ranges
+    }
+
+    // 1頂点分(座標3, 法線3, テクスチャ座標2)を追加
+    //  未指定や範囲外のインデックスは 0 で埋める
+    fn push_point(&self, buffer: &mut Vec<T>, point: &Point) {
+        let zero = get::<T>(0.0);
+        let lookup = |index: i32| {
+            if index < 0 {
+                None
+            } else {
+                Some(index as usize)
+            }
+        };
+
+        match lookup(point.vertex_index).and_then(|i| self.vertexes.get(i)) {
+            Some(vertex) => buffer.extend_from_slice(&[vertex.x, vertex.y, vertex.z]),
+            None => buffer.extend_from_slice(&[zero, zero, zero]),
+        }
+
+        match lookup(point.normal_index).and_then(|i| self.normals.get(i)) {
+            Some(normal) => buffer.extend_from_slice(&[normal.x, normal.y, normal.z]),
+            None => buffer.extend_from_slice(&[zero, zero, zero]),
+        }
+
+        match lookup(point.texture_coordinate_index).and_then(|i| self.texture_coordinates.get(i)) {
+            Some(texture_coordinate) => {
+                let v = if self.flip_v {
+                    get::<T>(1.0) - texture_coordinate.v // 左下原点(OpenGL座標)に変換
+                } else {
+                    texture_coordinate.v
+                };
+                buffer.extend_from_slice(&[texture_coordinate.u, v])
+            }
+            None => buffer.extend_from_slice(&[zero, zero]),
+        }
+    }
+
+    // (三角形数, マテリアル番号) のリスト(get_draw_ranges と同じ並び)
     pub fn get_surface_info(&self) -> Vec<(i32, i32)> {
-        let mut info = Vec::<(i32, i32)>::new();
-        for obj in &self.objects {
-            for grp in &obj.groups {
-                for surf in &grp.surfaces {
-                    for face in &surf.faces {
-                        info.push((face.points.len() as i32, surf.material_index));
-                    }
-                }
+        self.get_draw_ranges()
+            .iter()
+            .map(|range| ((range.count / 3) as i32, range.material_index))
+            .collect()
+    }
+
+    // テクスチャファイルのパス解決(マテリアルのディレクトリ、検索ディレクトリの順)
+    pub fn resolve_texture_path(&self, material: &Material<T>, filename: &str) -> PathBuf {
+        search_file(filename, &material.directory, &self.search_dirs)
+            .unwrap_or_else(|| material.directory.join(filename))
+    }
+
+    pub fn get_materials(&self) -> &[Material<T>] {
+        &self.materials
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    // 文字列から読み込む(mtllib は見つからない扱い)
+    pub(super) fn read_obj(
+        obj: &str,
+        options: &LoadOptions,
+    ) -> Result<(Box<Mesh<f32>>, Vec<Warning>), MeshError> {
+        let mut resolver =
+            |_: &str| -> io::Result<Box<dyn BufRead>> { Err(io::ErrorKind::NotFound.into()) };
+        Mesh::from_reader_with_options(Cursor::new(obj), &mut resolver, options)
+    }
+
+    fn vertex_indexes(mesh: &Mesh<f32>) -> Vec<i32> {
+        mesh.objects[0].groups[0].surfaces[0].faces[0]
+            .points
+            .iter()
+            .map(|p| p.vertex_index)
+            .collect()
+    }
+
+    #[test]
+    fn negative_indices_are_relative() {
+        let obj = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf -3 -2 -1\n";
+        let (mesh, _) = read_obj(obj, &LoadOptions::default()).unwrap();
+        assert_eq!(vertex_indexes(&mesh), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn empty_slots_are_unspecified() {
+        let obj = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nf 1//1 2//1 3//1\n";
+        let (mesh, _) = read_obj(obj, &LoadOptions::default()).unwrap();
+        let point = &mesh.objects[0].groups[0].surfaces[0].faces[0].points[0];
+        assert_eq!(point.texture_coordinate_index, -1);
+        assert_eq!(point.normal_index, 0);
+    }
+
+    #[test]
+    fn optional_vertex_components() {
+        let obj = "v 0 0 0 1\nv 1 0 0 1 0 0\nv 0 1 0\nvt 0.5\nvt 0.5 0.5 0\nf 1/1 2/2 3/2\n";
+        let (mesh, warnings) = read_obj(obj, &LoadOptions::default()).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(mesh.vertexes.len(), 3);
+        assert_eq!(mesh.texture_coordinates.len(), 2);
+    }
+
+    #[test]
+    fn missing_mtllib_reports_first_error() {
+        // 最初の "a" だけ PermissionDenied、連結した名前は NotFound
+        let mut resolver = |name: &str| -> io::Result<Box<dyn BufRead>> {
+            match name {
+                "a" => Err(io::ErrorKind::PermissionDenied.into()),
+                _ => Err(io::ErrorKind::NotFound.into()),
+            }
+        };
+        let result = Mesh::<f32>::from_reader_with_options(
+            Cursor::new("mtllib a b\n"),
+            &mut resolver,
+            &LoadOptions::default(),
+        );
+        match result {
+            Err(MeshError::Io { path, source }) => {
+                assert_eq!(path, "a");
+                assert_eq!(source.kind(), io::ErrorKind::PermissionDenied);
             }
+            _ => panic!("expected Io error"),
         }
-        info
     }
 
-    pub fn get_matrial(&self, material_index: i32) -> &Material<T> {
-        &self.materials[material_index as usize]
+    #[test]
+    fn transparency_is_inverse_of_dissolve() {
+        let mtl = "newmtl a\nd 0.25\nnewmtl b\nTr 0.25\n";
+        let mut resolver =
+            |_: &str| -> io::Result<Box<dyn BufRead>> { Ok(Box::new(Cursor::new(mtl))) };
+        let (mesh, _) = Mesh::<f32>::from_reader_with_options(
+            Cursor::new("mtllib test.mtl\n"),
+            &mut resolver,
+            &LoadOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(mesh.materials[0].alpha, 0.25);
+        assert_eq!(mesh.materials[1].alpha, 0.75);
+    }
+
+    #[test]
+    fn map_keywords_ignore_case() {
+        let mtl = "newmtl a\nmap_kd diffuse.png\nmap_Bump -bm 0.5 bump.png\nMAP_KE emissive.png\n";
+        let mut resolver =
+            |_: &str| -> io::Result<Box<dyn BufRead>> { Ok(Box::new(Cursor::new(mtl))) };
+        let (mesh, warnings) = Mesh::<f32>::from_reader_with_options(
+            Cursor::new("mtllib test.mtl\n"),
+            &mut resolver,
+            &LoadOptions::default(),
+        )
+        .unwrap();
+        assert!(warnings.is_empty());
+        let material = &mesh.materials[0];
+        assert_eq!(material.diffuse_map.filename, "diffuse.png");
+        assert_eq!(material.bump_map.filename, "bump.png");
+        assert_eq!(material.bump_map.bump_multiplier, 0.5);
+        assert_eq!(material.emissive_map.filename, "emissive.png");
+    }
+
+    #[test]
+    fn invalid_utf8_is_syntax_error() {
+        let obj: &[u8] = b"v 0 0 0\nv 1 0 0\ng \xff\nv 0 1 0\nf 1 2 3\n";
+        let mut resolver =
+            |_: &str| -> io::Result<Box<dyn BufRead>> { Err(io::ErrorKind::NotFound.into()) };
+        let result =
+            Mesh::<f32>::from_reader_with_options(obj, &mut resolver, &LoadOptions::default());
+        assert!(matches!(result, Err(MeshError::Syntax { line: 3, .. })));
+
+        let options = LoadOptions {
+            strict: false,
+            ..LoadOptions::default()
+        };
+        let (mesh, warnings) =
+            Mesh::<f32>::from_reader_with_options(obj, &mut resolver, &options).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].line, 3);
+        assert_eq!(mesh.vertexes.len(), 3);
+    }
+
+    #[test]
+    fn z_up_is_converted_to_y_up() {
+        let options = LoadOptions {
+            up_axis: UpAxis::Z,
+            ..LoadOptions::default()
+        };
+        let obj = "v 0 0 1\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nf 1//1 2//1 3//1\n";
+        let (mesh, _) = read_obj(obj, &options).unwrap();
+        let xyz = |v: &Vecter3D<f32>| (v.x, v.y, v.z);
+        assert_eq!(xyz(&mesh.vertexes[0]), (0.0, 1.0, 0.0));
+        assert_eq!(xyz(&mesh.vertexes[2]), (0.0, 0.0, -1.0));
+        assert_eq!(xyz(&mesh.normals[0]), (0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn flip_handedness_reverses_faces() {
+        let options = LoadOptions {
+            flip_handedness: true,
+            ..LoadOptions::default()
+        };
+        let obj = "v 0 0 0\nv 1 0 1\nv 0 1 0\nvn -1 0 1\nf 1//1 2//1 3//1\n";
+        let (mesh, _) = read_obj(obj, &options).unwrap();
+        assert_eq!(mesh.vertexes[1].z, -1.0);
+        let face = &mesh.objects[0].groups[0].surfaces[0].faces[0];
+        let order: Vec<i32> = face.points.iter().map(|p| p.vertex_index).collect();
+        assert_eq!(order, vec![2, 1, 0]);
+
+        // 面の向きと変換後の法線が一致
+        let normal = mesh.normals[0];
+        assert!(normal.x < 0.0 && normal.z < 0.0);
+        assert!(face.area_normal(&mesh.vertexes).normalize().dot(&normal) > 0.999);
+    }
+
+    #[test]
+    fn flip_v_only_affects_output() {
+        let obj = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0.1 0.7\nf 1/1 2/1 3/1\n";
+        let (mesh, _) = read_obj(obj, &LoadOptions::default()).unwrap();
+        assert_eq!(mesh.texture_coordinates[0].v, 0.7);
+        assert_eq!(mesh.get_vertex_array()[7], 1.0 - 0.7);
+
+        let mut output = Vec::new();
+        mesh.write_obj(&mut output).unwrap();
+        assert!(String::from_utf8(output).unwrap().contains("vt 0.1 0.7\n"));
+    }
+
+    #[test]
+    fn skipped_lines_keep_numbering() {
+        let options = LoadOptions {
+            strict: false,
+            ..LoadOptions::default()
+        };
+        let obj = "v 0 0 0\nv 1 x 0\nv 0 1 0\nvt 0 0\nvt a b\nvt 1 1\nf 1/1 2/2 3/3\n";
+        let (mesh, warnings) = read_obj(obj, &options).unwrap();
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(mesh.vertexes.len(), 3);
+        assert_eq!(mesh.texture_coordinates.len(), 3);
+        assert_eq!(mesh.vertexes[2].y, 1.0);
+        assert_eq!(mesh.get_vertex_array().len(), 3 * 8);
+    }
+
+    #[test]
+    fn out_of_range_indices_are_rejected() {
+        for face in ["f 1 2 9", "f 0 1 2", "f -4 1 2", "f 1/1 2 3"] {
+            let obj = format!("v 0 0 0\nv 1 0 0\nv 0 1 0\n{}\n", face);
+            match read_obj(&obj, &LoadOptions::default()) {
+                Err(MeshError::InvalidIndex { line, .. }) => assert_eq!(line, 4),
+                other => panic!("{}: {:?}", face, other.map(|_| ())),
+            }
+        }
     }
 }
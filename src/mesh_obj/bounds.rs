@@ -0,0 +1,158 @@
+use super::{get, Face, Mesh, Vecter3D};
+use num_traits::{Float, FromPrimitive};
+
+// 軸平行境界ボックス
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox<T: FromPrimitive> {
+    pub min: Vecter3D<T>,
+    pub max: Vecter3D<T>,
+}
+
+#[allow(dead_code)]
+impl<T: Float + FromPrimitive> BoundingBox<T> {
+    // 点群を囲むボックス(点が無ければ None)
+    fn from_points<'a, I: IntoIterator<Item = &'a Vecter3D<T>>>(points: I) -> Option<Self>
+    where
+        T: 'a,
+    {
+        let mut iter = points.into_iter();
+        let first = *iter.next()?;
+        let mut bounds = BoundingBox {
+            min: first,
+            max: first,
+        };
+        for p in iter {
+            bounds.min.x = bounds.min.x.min(p.x);
+            bounds.min.y = bounds.min.y.min(p.y);
+            bounds.min.z = bounds.min.z.min(p.z);
+            bounds.max.x = bounds.max.x.max(p.x);
+            bounds.max.y = bounds.max.y.max(p.y);
+            bounds.max.z = bounds.max.z.max(p.z);
+        }
+        Some(bounds)
+    }
+
+    pub fn center(&self) -> Vecter3D<T> {
+        self.min.add(&self.max).scale(get::<T>(0.5))
+    }
+
+    pub fn size(&self) -> Vecter3D<T> {
+        self.max.sub(&self.min)
+    }
+}
+
+// 境界球
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingSphere<T: FromPrimitive> {
+    pub center: Vecter3D<T>,
+    pub radius: T,
+}
+
+#[allow(dead_code)]
+impl<T: Float + FromPrimitive> BoundingSphere<T> {
+    // 点群を囲む球(中心はボックスの中心、点が無ければ None)
+    fn from_points(points: &[&Vecter3D<T>]) -> Option<Self> {
+        let bounds = BoundingBox::from_points(points.iter().copied())?;
+        let center = bounds.center();
+        let radius = points
+            .iter()
+            .map(|p| p.sub(&center).length())
+            .fold(T::zero(), T::max);
+        Some(BoundingSphere { center, radius })
+    }
+}
+
+#[allow(dead_code)]
+impl<T: Float + FromPrimitive> Mesh<T> {
+    // 面が参照する頂点座標(不正インデックスは無視)
+    fn face_positions<'a, I: IntoIterator<Item = &'a Face>>(
+        &'a self,
+        faces: I,
+    ) -> Vec<&'a Vecter3D<T>> {
+        faces
+            .into_iter()
+            .flat_map(|face| &face.points)
+            .filter_map(|p| self.vertexes.get(p.vertex_index as usize))
+            .collect()
+    }
+
+    fn mesh_positions(&self) -> Vec<&Vecter3D<T>> {
+        self.face_positions(
+            self.objects
+                .iter()
+                .flat_map(|obj| &obj.groups)
+                .flat_map(|grp| &grp.surfaces)
+                .flat_map(|surf| &surf.faces),
+        )
+    }
+
+    fn object_positions(&self, object_index: usize) -> Vec<&Vecter3D<T>> {
+        match self.objects.get(object_index) {
+            Some(obj) => self.face_positions(
+                obj.groups
+                    .iter()
+                    .flat_map(|grp| &grp.surfaces)
+                    .flat_map(|surf| &surf.faces),
+            ),
+            None => Vec::new(),
+        }
+    }
+
+    fn group_positions(&self, object_index: usize, group_index: usize) -> Vec<&Vecter3D<T>> {
+        match self
+            .objects
+            .get(object_index)
+            .and_then(|obj| obj.groups.get(group_index))
+        {
+            Some(grp) => self.face_positions(grp.surfaces.iter().flat_map(|surf| &surf.faces)),
+            None => Vec::new(),
+        }
+    }
+
+    // メッシュ全体の境界(面の無いメッシュなら None)
+    pub fn get_bounding_box(&self) -> Option<BoundingBox<T>> {
+        BoundingBox::from_points(self.mesh_positions())
+    }
+
+    pub fn get_bounding_sphere(&self) -> Option<BoundingSphere<T>> {
+        BoundingSphere::from_points(&self.mesh_positions())
+    }
+
+    // オブジェクト毎の境界
+    pub fn get_object_count(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn get_object_bounding_box(&self, object_index: usize) -> Option<BoundingBox<T>> {
+        BoundingBox::from_points(self.object_positions(object_index))
+    }
+
+    pub fn get_object_bounding_sphere(&self, object_index: usize) -> Option<BoundingSphere<T>> {
+        BoundingSphere::from_points(&self.object_positions(object_index))
+    }
+
+    // グループ毎の境界
+    pub fn get_group_count(&self, object_index: usize) -> usize {
+        self.objects
+            .get(object_index)
+            .map_or(0, |obj| obj.groups.len())
+    }
+
+    pub fn get_group_bounding_box(
+        &self,
+        object_index: usize,
+        group_index: usize,
+    ) -> Option<BoundingBox<T>> {
+        BoundingBox::from_points(self.group_positions(object_index, group_index))
+    }
+
+    pub fn get_group_bounding_sphere(
+        &self,
+        object_index: usize,
+        group_index: usize,
+    ) -> Option<BoundingSphere<T>> {
+        BoundingSphere::from_points(&self.group_positions(object_index, group_index))
+    }
+}
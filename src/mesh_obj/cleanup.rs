@@ -0,0 +1,270 @@
+use super::{get, Face, Mesh, Point, Vecter3D};
+use num_traits::{Float, FromPrimitive};
+use std::collections::{HashMap, HashSet};
+
+// 整理オプション
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct CleanupOptions {
+    pub weld_distance: f64,            // 頂点を結合する距離(0 以下なら結合しない)
+    pub remove_unused: bool,           // 参照されない頂点/法線/テクスチャ座標/接線を削除
+    pub remove_degenerate_faces: bool, // 縮退面(3点未満、頂点の重複、面積0)を削除
+    pub remove_duplicate_faces: bool,  // 頂点の並びが同じ面を削除(裏向きの面は残す)
+    pub remove_empty: bool,            // 空のサーフェース/グループ/オブジェクトを削除
+}
+
+impl Default for CleanupOptions {
+    fn default() -> Self {
+        CleanupOptions {
+            weld_distance: 1.0e-6,
+            remove_unused: true,
+            remove_degenerate_faces: true,
+            remove_duplicate_faces: true,
+            remove_empty: true,
+        }
+    }
+}
+
+// 整理結果(削除/結合した個数)
+#[allow(dead_code)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CleanupReport {
+    pub welded_vertexes: usize,
+    pub removed_vertexes: usize,
+    pub removed_normals: usize,
+    pub removed_texture_coordinates: usize,
+    pub removed_tangents: usize,
+    pub removed_faces: usize,
+}
+
+// 使われている要素だけを残して詰める(古い番号 → 新しい番号、削除したものは -1)
+fn compact<V>(items: &mut Vec<V>, used: &[bool]) -> Vec<i32> {
+    let mut remap = Vec::with_capacity(items.len());
+    let mut count = 0;
+    for &u in used {
+        remap.push(if u { count } else { -1 });
+        if u {
+            count += 1;
+        }
+    }
+    let mut i = 0;
+    items.retain(|_| {
+        i += 1;
+        used[i - 1]
+    });
+    remap
+}
+
+// インデックスの付け替え(範囲外/未指定はそのまま)
+fn remap_index(index: &mut i32, remap: &[i32]) {
+    if let Some(&new_index) = remap.get(*index as usize) {
+        *index = new_index;
+    }
+}
+
+#[allow(dead_code)]
+impl<T: Float + FromPrimitive> Mesh<T> {
+    // メッシュの整理(結合、縮退/重複面の削除、未使用要素の削除を行いインデックスを付け替える)
+    pub fn cleanup(&mut self, options: &CleanupOptions) -> CleanupReport {
+        let mut report = CleanupReport::default();
+
+        // 頂点の結合
+        if options.weld_distance > 0.0 {
+            let remap = self.weld_vertexes(get::<T>(options.weld_distance));
+            report.welded_vertexes = remap
+                .iter()
+                .enumerate()
+                .filter(|&(i, &j)| i as i32 != j)
+                .count();
+            for point in self.points_mut() {
+                remap_index(&mut point.vertex_index, &remap);
+            }
+        }
+
+        // 面の削除
+        let vertexes = &self.vertexes;
+        let mut seen: HashSet<Vec<i32>> = HashSet::new();
+        for obj in &mut self.objects {
+            for grp in &mut obj.groups {
+                for surf in &mut grp.surfaces {
+                    let before = surf.faces.len();
+                    surf.faces.retain_mut(|face| {
+                        if options.remove_degenerate_faces && face.remove_repeated_points() {
+                            return false;
+                        }
+                        if options.remove_degenerate_faces && face.is_zero_area(vertexes) {
+                            return false;
+                        }
+                        if options.remove_duplicate_faces {
+                            return seen.insert(face.winding_key());
+                        }
+                        true
+                    });
+                    report.removed_faces += before - surf.faces.len();
+                }
+            }
+        }
+
+        // 未使用要素の削除
+        if options.remove_unused {
+            let mut used_vertexes = vec![false; self.vertexes.len()];
+            let mut used_normals = vec![false; self.normals.len()];
+            let mut used_texture_coordinates = vec![false; self.texture_coordinates.len()];
+            let mut used_tangents = vec![false; self.tangents.len()];
+            for point in self.points_mut() {
+                let mark = |used: &mut Vec<bool>, index: i32| {
+                    if let Some(u) = used.get_mut(index as usize) {
+                        *u = true;
+                    }
+                };
+                mark(&mut used_vertexes, point.vertex_index);
+                mark(&mut used_normals, point.normal_index);
+                mark(
+                    &mut used_texture_coordinates,
+                    point.texture_coordinate_index,
+                );
+                mark(&mut used_tangents, point.tangent_index);
+            }
+
+            let count = |used: &[bool]| used.iter().filter(|&&u| !u).count();
+            report.removed_vertexes = count(&used_vertexes);
+            report.removed_normals = count(&used_normals);
+            report.removed_texture_coordinates = count(&used_texture_coordinates);
+            report.removed_tangents = count(&used_tangents);
+
+            let vertex_remap = compact(&mut self.vertexes, &used_vertexes);
+            let normal_remap = compact(&mut self.normals, &used_normals);
+            let texture_remap = compact(&mut self.texture_coordinates, &used_texture_coordinates);
+            let tangent_remap = compact(&mut self.tangents, &used_tangents);
+            for point in self.points_mut() {
+                remap_index(&mut point.vertex_index, &vertex_remap);
+                remap_index(&mut point.normal_index, &normal_remap);
+                remap_index(&mut point.texture_coordinate_index, &texture_remap);
+                remap_index(&mut point.tangent_index, &tangent_remap);
+            }
+        }
+
+        // 空のサーフェース/グループ/オブジェクトの削除
+        if options.remove_empty {
+            for obj in &mut self.objects {
+                for grp in &mut obj.groups {
+                    grp.surfaces.retain(|surf| !surf.empty());
+                }
+                obj.groups.retain(|grp| !grp.empty());
+            }
+            self.objects.retain(|obj| !obj.empty());
+        }
+
+        report
+    }
+
+    // 全ての点
+    fn points_mut(&mut self) -> impl Iterator<Item = &mut Point> {
+        self.objects
+            .iter_mut()
+            .flat_map(|obj| &mut obj.groups)
+            .flat_map(|grp| &mut grp.surfaces)
+            .flat_map(|surf| &mut surf.faces)
+            .flat_map(|face| &mut face.points)
+    }
+
+    // 距離 distance 以内の頂点を先に出てきた頂点にまとめる(古い番号 → 代表の番号)
+    //  格子に振り分けて隣接セルだけを比較する
+    fn weld_vertexes(&self, distance: T) -> Vec<i32> {
+        let cell = |v: &Vecter3D<T>| {
+            let c = |x: T| (x / distance).floor().to_i64();
+            match (c(v.x), c(v.y), c(v.z)) {
+                (Some(x), Some(y), Some(z)) => Some((x, y, z)),
+                _ => None, // NaN 等は結合しない
+            }
+        };
+
+        let mut grid: HashMap<(i64, i64, i64), Vec<usize>> = HashMap::new();
+        let mut remap = Vec::with_capacity(self.vertexes.len());
+        for (i, v) in self.vertexes.iter().enumerate() {
+            let key = match cell(v) {
+                Some(key) => key,
+                None => {
+                    remap.push(i as i32);
+                    continue;
+                }
+            };
+
+            let mut found = None;
+            'search: for dx in -1..=1 {
+                for dy in -1..=1 {
+                    for dz in -1..=1 {
+                        let neighbor = (key.0 + dx, key.1 + dy, key.2 + dz);
+                        for &j in grid.get(&neighbor).into_iter().flatten() {
+                            if self.vertexes[j].sub(v).length() <= distance {
+                                found = Some(j);
+                                break 'search;
+                            }
+                        }
+                    }
+                }
+            }
+
+            match found {
+                Some(j) => remap.push(j as i32),
+                None => {
+                    grid.entry(key).or_default().push(i);
+                    remap.push(i as i32);
+                }
+            }
+        }
+        remap
+    }
+}
+
+#[allow(dead_code)]
+impl Face {
+    // 連続する同じ頂点をまとめ、縮退(3点未満 or 頂点の重複)していれば true
+    fn remove_repeated_points(&mut self) -> bool {
+        self.points.dedup_by_key(|p| p.vertex_index);
+        while self.points.len() > 1
+            && self.points.first().map(|p| p.vertex_index)
+                == self.points.last().map(|p| p.vertex_index)
+        {
+            self.points.pop();
+        }
+        let mut indexes: Vec<i32> = self.points.iter().map(|p| p.vertex_index).collect();
+        indexes.sort_unstable();
+        indexes.len() < 3 || indexes.windows(2).any(|w| w[0] == w[1])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::read_obj;
+    use super::super::LoadOptions;
+    use super::*;
+
+    fn load(obj: &str) -> Box<Mesh<f32>> {
+        read_obj(obj, &LoadOptions::default()).unwrap().0
+    }
+
+    #[test]
+    fn duplicate_faces_keep_back_faces() {
+        let obj = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\nf 2 3 1\nf 3 2 1\n";
+        let mut mesh = load(obj);
+        let report = mesh.cleanup(&CleanupOptions::default());
+        assert_eq!(report.removed_faces, 1);
+        let windings: Vec<Vec<i32>> = mesh.objects[0].groups[0].surfaces[0]
+            .faces
+            .iter()
+            .map(|face| face.winding_key())
+            .collect();
+        assert_eq!(windings, vec![vec![0, 1, 2], vec![0, 2, 1]]);
+    }
+
+    #[test]
+    fn weld_and_compact() {
+        let obj = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 0.0000001 0\nv 5 5 5\nf 1 2 3\nf 1 4 3\n";
+        let mut mesh = load(obj);
+        let report = mesh.cleanup(&CleanupOptions::default());
+        assert_eq!(report.welded_vertexes, 1);
+        assert_eq!(report.removed_faces, 1);
+        assert_eq!(mesh.vertexes.len(), 3);
+    }
+}
@@ -0,0 +1,155 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+// OBJ/MTL 読み込みエラー
+#[allow(dead_code)]
+#[derive(Debug)]
+pub enum MeshError {
+    // ファイルオープン/読み込み失敗
+    Io {
+        path: String,
+        source: io::Error,
+    },
+    // 数値や書式の誤り
+    Syntax {
+        path: String,
+        line: usize,
+        column: usize,
+        token: String,
+    },
+    // 未知のコマンド(もしくは引数の数が不正)
+    UnknownDirective {
+        path: String,
+        line: usize,
+        column: usize,
+        token: String,
+    },
+    // 範囲外のインデックス
+    InvalidIndex {
+        path: String,
+        line: usize,
+        column: usize,
+        token: String,
+    },
+}
+
+#[allow(dead_code)]
+impl MeshError {
+    pub fn path(&self) -> &str {
+        match self {
+            MeshError::Io { path, .. }
+            | MeshError::Syntax { path, .. }
+            | MeshError::UnknownDirective { path, .. }
+            | MeshError::InvalidIndex { path, .. } => path,
+        }
+    }
+
+    // 行番号(1始まり)
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            MeshError::Io { .. } => None,
+            MeshError::Syntax { line, .. }
+            | MeshError::UnknownDirective { line, .. }
+            | MeshError::InvalidIndex { line, .. } => Some(*line),
+        }
+    }
+
+    // 桁位置(1始まり)
+    pub fn column(&self) -> Option<usize> {
+        match self {
+            MeshError::Io { .. } => None,
+            MeshError::Syntax { column, .. }
+            | MeshError::UnknownDirective { column, .. }
+            | MeshError::InvalidIndex { column, .. } => Some(*column),
+        }
+    }
+
+    // 問題のあったトークン
+    pub fn token(&self) -> Option<&str> {
+        match self {
+            MeshError::Io { .. } => None,
+            MeshError::Syntax { token, .. }
+            | MeshError::UnknownDirective { token, .. }
+            | MeshError::InvalidIndex { token, .. } => Some(token),
+        }
+    }
+}
+
+impl fmt::Display for MeshError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MeshError::Io { path, source } => write!(f, "{}: {}", path, source),
+            MeshError::Syntax {
+                path,
+                line,
+                column,
+                token,
+            } => write!(f, "{}[{}:{}]: parse error: {}", path, line, column, token),
+            MeshError::UnknownDirective {
+                path,
+                line,
+                column,
+                token,
+            } => write!(
+                f,
+                "{}[{}:{}]: unknown directive: {}",
+                path, line, column, token
+            ),
+            MeshError::InvalidIndex {
+                path,
+                line,
+                column,
+                token,
+            } => write!(f, "{}[{}:{}]: invalid index: {}", path, line, column, token),
+        }
+    }
+}
+
+impl Error for MeshError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MeshError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+// 警告の種類
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarningKind {
+    Syntax,             // 数値や書式の誤り
+    UnknownDirective,   // 未知のコマンド
+    InvalidIndex,       // 範囲外のインデックス
+    MissingFile,        // 参照ファイルが開けない
+    UnresolvedMaterial, // usemtl の名前がどの mtllib にも無い
+}
+
+// 読み飛ばした行の警告(非strictモード)
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub path: String,
+    pub line: usize,
+    pub column: usize,
+    pub token: String,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self.kind {
+            WarningKind::Syntax => "parse error",
+            WarningKind::UnknownDirective => "unknown directive",
+            WarningKind::InvalidIndex => "invalid index",
+            WarningKind::MissingFile => "couldn't open",
+            WarningKind::UnresolvedMaterial => "undefined material",
+        };
+        write!(
+            f,
+            "{}[{}:{}]: {}: {}",
+            self.path, self.line, self.column, message, self.token
+        )
+    }
+}
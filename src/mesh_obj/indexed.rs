@@ -0,0 +1,253 @@
+use super::{Mesh, Point};
+use num_traits::{Float, FromPrimitive, ToPrimitive};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+// 描画範囲(同一マテリアルの連続した範囲)
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DrawRange {
+    pub material_index: i32, // マテリアル番号(-1 なら未指定)
+    pub start: usize,        // 先頭位置(インデックス or 頂点の番号)
+    pub count: usize,        // 個数
+}
+
+// インデックス付き頂点配列
+#[allow(dead_code)]
+pub struct IndexedArrays<T, I> {
+    pub vertices: Vec<T>,       // 頂点配列(座標3, 法線3, テクスチャ座標2)
+    pub indices: Vec<I>,        // インデックス配列(三角形リスト)
+    pub ranges: Vec<DrawRange>, // マテリアル毎のインデックス範囲
+}
+
+// 1頂点あたりの要素数(座標3, 法線3, テクスチャ座標2)
+const VERTEX_SIZE: usize = 8;
+
+// 頂点の同一判定キー(頂点, 法線, テクスチャ座標)
+type VertexKey = (i32, i32, i32);
+
+fn vertex_key(point: &Point) -> VertexKey {
+    (
+        point.vertex_index,
+        point.normal_index,
+        point.texture_coordinate_index,
+    )
+}
+
+#[allow(dead_code)]
+impl<T: FromStr + Float + FromPrimitive + ToPrimitive> Mesh<T> {
+    // マテリアル毎の三角形リスト(マテリアルは初出順)
+    pub(super) fn material_triangles(&self) -> Vec<(i32, Vec<[&Point; 3]>)> {
+        let mut groups: Vec<(i32, Vec<[&Point; 3]>)> = Vec::new();
+        for obj in &self.objects {
+            for grp in &obj.groups {
+                for surf in &grp.surfaces {
+                    let pos = match groups.iter().position(|(m, _)| *m == surf.material_index) {
+                        Some(pos) => pos,
+                        None => {
+                            groups.push((surf.material_index, Vec::new()));
+                            groups.len() - 1
+                        }
+                    };
+                    for face in &surf.faces {
+                        for triangle in face.triangulate(&self.vertexes) {
+                            groups[pos].1.push(triangle.map(|i| &face.points[i]));
+                        }
+                    }
+                }
+            }
+        }
+        groups.retain(|(_, triangles)| !triangles.is_empty());
+        groups
+    }
+
+    // 重複頂点をまとめたインデックス付き頂点配列
+    pub fn get_indexed_arrays(&self) -> IndexedArrays<T, u32> {
+        self.build_batches(usize::MAX)
+            .pop()
+            .unwrap_or_else(IndexedArrays::new)
+    }
+
+    // 16bitインデックス用に分割したインデックス付き頂点配列
+    //  1バッチの頂点数は max_vertices(最大 65535)以下
+    //  描画範囲がマテリアルをまたぐことは無い
+    pub fn get_indexed_batches(&self, max_vertices: usize) -> Vec<IndexedArrays<T, u16>> {
+        let max_vertices = max_vertices.max(3).min(u16::MAX as usize);
+        self.build_batches(max_vertices)
+            .into_iter()
+            .map(|batch| IndexedArrays {
+                vertices: batch.vertices,
+                indices: batch.indices.iter().map(|&i| i as u16).collect(),
+                ranges: batch.ranges,
+            })
+            .collect()
+    }
+
+    // 頂点数の上限でバッチに分割
+    fn build_batches(&self, max_vertices: usize) -> Vec<IndexedArrays<T, u32>> {
+        let mut batches = Vec::new();
+        let mut batch = IndexedArrays::new();
+        let mut vertex_index: HashMap<VertexKey, u32> = HashMap::new();
+
+        for (material_index, triangles) in self.material_triangles() {
+            let mut start = batch.indices.len();
+            for triangle in &triangles {
+                // 追加で必要になる頂点数
+                let mut keys: Vec<VertexKey> = triangle
+                    .iter()
+                    .map(|p| vertex_key(p))
+                    .filter(|key| !vertex_index.contains_key(key))
+                    .collect();
+                keys.sort_unstable();
+                keys.dedup();
+
+                // 入りきらなければ新しいバッチへ
+                let vertex_count = batch.vertices.len() / VERTEX_SIZE;
+                if vertex_count + keys.len() > max_vertices {
+                    batch.close_range(material_index, start);
+                    batches.push(std::mem::replace(&mut batch, IndexedArrays::new()));
+                    vertex_index.clear();
+                    start = 0;
+                }
+
+                for point in triangle {
+                    let index = *vertex_index.entry(vertex_key(point)).or_insert_with(|| {
+                        self.push_point(&mut batch.vertices, point);
+                        (batch.vertices.len() / VERTEX_SIZE) as u32 - 1
+                    });
+                    batch.indices.push(index);
+                }
+            }
+            batch.close_range(material_index, start);
+        }
+
+        if !batch.indices.is_empty() {
+            batches.push(batch);
+        }
+        batches
+    }
+}
+
+#[allow(dead_code)]
+impl<T, I> IndexedArrays<T, I> {
+    fn new() -> Self {
+        IndexedArrays {
+            vertices: Vec::new(),
+            indices: Vec::new(),
+            ranges: Vec::new(),
+        }
+    }
+
+    // start から末尾までを描画範囲として登録
+    fn close_range(&mut self, material_index: i32, start: usize) {
+        if self.indices.len() > start {
+            self.ranges.push(DrawRange {
+                material_index,
+                start,
+                count: self.indices.len() - start,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{LoadOptions, Mesh};
+    use super::*;
+    use std::collections::HashMap;
+    use std::io::{self, BufRead, Cursor};
+
+    // 2マテリアル(a, b)の帯状メッシュ(四角形 8 枚、a と b が交互)
+    fn two_material_strip() -> Box<Mesh<f32>> {
+        let mut obj = String::from("mtllib test.mtl\n");
+        for i in 0..9 {
+            obj += &format!("v {} 0 0\nv {} 1 0\n", i, i);
+        }
+        for i in 0..8 {
+            let v = i * 2 + 1;
+            obj += &format!(
+                "usemtl {}\nf {} {} {} {}\n",
+                if i % 2 == 0 { "a" } else { "b" },
+                v,
+                v + 2,
+                v + 3,
+                v + 1
+            );
+        }
+        let mut resolver = |_: &str| -> io::Result<Box<dyn BufRead>> {
+            Ok(Box::new(Cursor::new("newmtl a\nnewmtl b\n")))
+        };
+        Mesh::from_reader_with_options(Cursor::new(obj), &mut resolver, &LoadOptions::default())
+            .unwrap()
+            .0
+    }
+
+    #[test]
+    fn shared_corners_are_merged() {
+        // 1-2 面目は (v, vn, vt) が共通、3 面目は法線、4 面目はテクスチャ座標が違う
+        let obj = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvn 0 0 1\nvn 1 0 0\nvt 0 0\nvt 1 1\n\
+                   f 1//1 2//1 3//1\nf 1//1 3//1 4//1\nf 1//2 2//2 3//2\nf 1/2/1 2//1 3//1\n";
+        let options = LoadOptions {
+            generate_normals: false,
+            ..LoadOptions::default()
+        };
+        let mesh = super::super::tests::read_obj(obj, &options).unwrap().0;
+        let arrays = mesh.get_indexed_arrays();
+        assert_eq!(arrays.indices.len(), 12);
+        assert_eq!(arrays.vertices.len() / VERTEX_SIZE, 4 + 3 + 1);
+        assert_eq!(&arrays.indices[..6], &[0, 1, 2, 0, 2, 3]);
+        assert_eq!(&arrays.indices[9..], &[7, 1, 2]);
+
+        // 展開すると get_vertex_array と同じ
+        let expanded: Vec<f32> = arrays
+            .indices
+            .iter()
+            .flat_map(|&i| {
+                let i = i as usize * VERTEX_SIZE;
+                arrays.vertices[i..i + VERTEX_SIZE].to_vec()
+            })
+            .collect();
+        assert_eq!(expanded, mesh.get_vertex_array());
+    }
+
+    #[test]
+    fn batches_respect_vertex_limit_and_materials() {
+        let mesh = two_material_strip();
+        let max_vertices = 5;
+        let batches = mesh.get_indexed_batches(max_vertices);
+        assert!(batches.len() > 1);
+
+        // マテリアル毎に展開した頂点列
+        let mut expanded: HashMap<i32, Vec<f32>> = HashMap::new();
+        for batch in &batches {
+            let vertex_count = batch.vertices.len() / VERTEX_SIZE;
+            assert!(vertex_count <= max_vertices);
+            assert!(batch.indices.iter().all(|&i| (i as usize) < vertex_count));
+
+            let mut end = 0;
+            for range in &batch.ranges {
+                assert_eq!(range.start, end); // 範囲は隙間無く並ぶ
+                end = range.start + range.count;
+                for &i in &batch.indices[range.start..end] {
+                    let i = i as usize * VERTEX_SIZE;
+                    expanded
+                        .entry(range.material_index)
+                        .or_default()
+                        .extend_from_slice(&batch.vertices[i..i + VERTEX_SIZE]);
+                }
+            }
+            assert_eq!(end, batch.indices.len());
+        }
+
+        // 範囲のマテリアルが get_vertex_array の描画範囲と一致する
+        let vertex_array = mesh.get_vertex_array();
+        let ranges = mesh.get_draw_ranges();
+        let materials: Vec<i32> = ranges.iter().map(|range| range.material_index).collect();
+        assert_eq!(materials, vec![0, 1]);
+        for range in ranges {
+            let start = range.start * VERTEX_SIZE;
+            let end = start + range.count * VERTEX_SIZE;
+            assert_eq!(expanded[&range.material_index], vertex_array[start..end]);
+        }
+    }
+}
@@ -0,0 +1,327 @@
+use super::{Face, Mesh, Point, Vecter3D};
+use num_traits::{Float, FromPrimitive};
+use std::collections::HashMap;
+
+#[allow(dead_code)]
+impl Face {
+    // Newell法による面の法線(長さは面積の2倍、不正インデックスの点は無視)
+    pub(super) fn area_normal<T: Float + FromPrimitive>(
+        &self,
+        vertexes: &[Vecter3D<T>],
+    ) -> Vecter3D<T> {
+        let positions: Vec<&Vecter3D<T>> = self
+            .points
+            .iter()
+            .filter(|p| p.vertex_index >= 0 && (p.vertex_index as usize) < vertexes.len())
+            .map(|p| &vertexes[p.vertex_index as usize])
+            .collect();
+
+        let zero = T::zero();
+        let mut normal = Vecter3D {
+            x: zero,
+            y: zero,
+            z: zero,
+        };
+        let n = positions.len();
+        for i in 0..n {
+            let a = positions[i];
+            let b = positions[(i + 1) % n];
+            normal.x = normal.x + (a.y - b.y) * (a.z + b.z);
+            normal.y = normal.y + (a.z - b.z) * (a.x + b.x);
+            normal.z = normal.z + (a.x - b.x) * (a.y + b.y);
+        }
+        normal
+    }
+
+    // 各頂点の角度(ラジアン)
+    pub(super) fn corner_angles<T: Float + FromPrimitive>(
+        &self,
+        vertexes: &[Vecter3D<T>],
+    ) -> Vec<T> {
+        let n = self.points.len();
+        (0..n)
+            .map(|i| {
+                let prev = &self.points[(i + n - 1) % n];
+                let cur = &self.points[i];
+                let next = &self.points[(i + 1) % n];
+                let get = |p: &Point| vertexes.get(p.vertex_index as usize).copied();
+                match (get(prev), get(cur), get(next)) {
+                    (Some(a), Some(b), Some(c)) => {
+                        let u = a.sub(&b).normalize();
+                        let v = c.sub(&b).normalize();
+                        u.dot(&v).max(-T::one()).min(T::one()).acos()
+                    }
+                    _ => T::zero(),
+                }
+            })
+            .collect()
+    }
+}
+
+#[allow(dead_code)]
+impl<T: Float + FromPrimitive> Mesh<T> {
+    // 法線生成
+    //  - スムージンググループ外の面はフラット法線
+    //  - グループ内は面積と角度で重み付けした法線を共有
+    //  - recompute なら既存の法線も全て作り直す
+    pub fn generate_normals(&mut self, recompute: bool) {
+        if recompute {
+            self.normals.clear();
+        }
+
+        // スムージンググループ毎に頂点法線を集計
+        let mut smooth: HashMap<(i32, u32), Vecter3D<T>> = HashMap::new();
+        for obj in &self.objects {
+            for grp in &obj.groups {
+                for surf in &grp.surfaces {
+                    for face in &surf.faces {
+                        if face.smoothing_group == 0 {
+                            continue;
+                        }
+                        let normal = face.area_normal(&self.vertexes);
+                        let angles = face.corner_angles(&self.vertexes);
+                        for (point, angle) in face.points.iter().zip(angles) {
+                            let key = (point.vertex_index, face.smoothing_group);
+                            let sum = smooth.entry(key).or_insert_with(Vecter3D::new);
+                            *sum = sum.add(&normal.scale(angle));
+                        }
+                    }
+                }
+            }
+        }
+
+        // 法線の割り当て
+        let mut smooth_index: HashMap<(i32, u32), i32> = HashMap::new();
+        let normals = &mut self.normals;
+        let vertexes = &self.vertexes;
+        for obj in &mut self.objects {
+            for grp in &mut obj.groups {
+                for surf in &mut grp.surfaces {
+                    for face in &mut surf.faces {
+                        let mut flat_index = -1;
+                        for i in 0..face.points.len() {
+                            if !recompute && face.points[i].normal_index >= 0 {
+                                continue;
+                            }
+                            let index = if face.smoothing_group == 0 {
+                                if flat_index < 0 {
+                                    flat_index = normals.len() as i32;
+                                    normals.push(face.area_normal(vertexes).normalize());
+                                }
+                                flat_index
+                            } else {
+                                let key = (face.points[i].vertex_index, face.smoothing_group);
+                                *smooth_index.entry(key).or_insert_with(|| {
+                                    normals.push(smooth[&key].normalize());
+                                    normals.len() as i32 - 1
+                                })
+                            };
+                            face.points[i].normal_index = index;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // 折り目角度による法線の再計算
+    //  隣接面の法線の成す角が crease_angle(ラジアン)を超える所で法線を分割する
+    //  法線が変わるので接線は破棄する(必要なら generate_tangents で作り直す)
+    pub fn recompute_normals(&mut self, crease_angle: T) {
+        let threshold = crease_angle.cos();
+
+        // 面法線と各頂点の重み付き法線を求める
+        let mut face_normals = Vec::new(); // 正規化した面法線
+        let mut corners = Vec::new(); // (頂点番号, 面番号, 重み付き法線)
+        for obj in &self.objects {
+            for grp in &obj.groups {
+                for surf in &grp.surfaces {
+                    for face in &surf.faces {
+                        let normal = face.area_normal(&self.vertexes);
+                        let angles = face.corner_angles(&self.vertexes);
+                        for (point, angle) in face.points.iter().zip(angles) {
+                            corners.push((
+                                point.vertex_index,
+                                face_normals.len(),
+                                normal.scale(angle),
+                            ));
+                        }
+                        face_normals.push(normal.normalize());
+                    }
+                }
+            }
+        }
+
+        // 頂点毎に共有している角をまとめる
+        let mut incident: HashMap<i32, Vec<usize>> = HashMap::new();
+        for (i, corner) in corners.iter().enumerate() {
+            incident.entry(corner.0).or_default().push(i);
+        }
+
+        // 折り目角度以内の面だけを平均して法線を作る(同じ法線は共有)
+        let mut normals = Vec::new();
+        let mut normal_index: HashMap<[(u64, i16, i8); 3], i32> = HashMap::new();
+        let mut corner_normal = Vec::with_capacity(corners.len());
+        for (vertex_index, face_index, _) in &corners {
+            let base = &face_normals[*face_index];
+            let mut sum = Vecter3D::new();
+            for &j in &incident[vertex_index] {
+                let (_, other, weighted) = &corners[j];
+                if base.dot(&face_normals[*other]) >= threshold {
+                    sum = sum.add(weighted);
+                }
+            }
+            let normal = if sum.length() > T::zero() {
+                sum.normalize()
+            } else {
+                *base
+            };
+
+            let key = [
+                normal.x.integer_decode(),
+                normal.y.integer_decode(),
+                normal.z.integer_decode(),
+            ];
+            let index = *normal_index.entry(key).or_insert_with(|| {
+                normals.push(normal);
+                normals.len() as i32 - 1
+            });
+            corner_normal.push(index);
+        }
+
+        // 法線インデックスの付け替え
+        let mut indexes = corner_normal.into_iter();
+        for obj in &mut self.objects {
+            for grp in &mut obj.groups {
+                for surf in &mut grp.surfaces {
+                    for face in &mut surf.faces {
+                        for point in &mut face.points {
+                            point.normal_index = indexes.next().unwrap();
+                            point.tangent_index = -1;
+                        }
+                    }
+                }
+            }
+        }
+        self.normals = normals;
+        self.tangents.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::read_obj;
+    use super::super::{LoadOptions, Mesh, Vecter3D};
+
+    // 辺 1-2 で 90° に折れた2面(+Z 向きと -Y 向き)
+    const FOLD: &str = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 0 0 -1\n";
+
+    // 全ての面の法線インデックス
+    fn normal_indexes(mesh: &Mesh<f32>) -> Vec<Vec<i32>> {
+        mesh.objects
+            .iter()
+            .flat_map(|obj| &obj.groups)
+            .flat_map(|grp| &grp.surfaces)
+            .flat_map(|surf| &surf.faces)
+            .map(|face| face.points.iter().map(|p| p.normal_index).collect())
+            .collect()
+    }
+
+    fn assert_near(v: &Vecter3D<f32>, x: f32, y: f32, z: f32) {
+        let d = (v.x - x).abs() + (v.y - y).abs() + (v.z - z).abs();
+        assert!(
+            d < 1.0e-5,
+            "({}, {}, {}) != ({}, {}, {})",
+            v.x,
+            v.y,
+            v.z,
+            x,
+            y,
+            z
+        );
+    }
+
+    #[test]
+    fn smoothing_groups_share_normals() {
+        let obj = format!(
+            "{}v 5 0 0\nv 6 0 0\nv 5 0 1\ns 1\nf 1 2 3\nf 2 1 4\ns off\nf 5 6 7\n",
+            FOLD
+        );
+        let mesh = read_obj(&obj, &LoadOptions::default()).unwrap().0;
+        let indexes = normal_indexes(&mesh);
+
+        // 共有頂点 1, 2 は平均した法線を共有
+        assert_eq!(indexes[0][0], indexes[1][1]);
+        assert_eq!(indexes[0][1], indexes[1][0]);
+        let h = 0.5f32.sqrt();
+        assert_near(&mesh.normals[indexes[0][0] as usize], 0.0, -h, h);
+        assert_near(&mesh.normals[indexes[0][1] as usize], 0.0, -h, h);
+        assert_near(&mesh.normals[indexes[0][2] as usize], 0.0, 0.0, 1.0);
+        assert_near(&mesh.normals[indexes[1][2] as usize], 0.0, -1.0, 0.0);
+
+        // s off の面はフラット
+        assert!(indexes[2].iter().all(|&i| i == indexes[2][0]));
+        assert_near(&mesh.normals[indexes[2][0] as usize], 0.0, -1.0, 0.0);
+    }
+
+    #[test]
+    fn recompute_overrides_file_normals() {
+        let obj = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 1 0 0\nf 1//1 2//1 3//1\n";
+        let mesh = read_obj(obj, &LoadOptions::default()).unwrap().0;
+        assert_near(
+            &mesh.normals[normal_indexes(&mesh)[0][0] as usize],
+            1.0,
+            0.0,
+            0.0,
+        );
+
+        let options = LoadOptions {
+            recompute_normals: true,
+            ..LoadOptions::default()
+        };
+        let mesh = read_obj(obj, &options).unwrap().0;
+        for index in &normal_indexes(&mesh)[0] {
+            assert_near(&mesh.normals[*index as usize], 0.0, 0.0, 1.0);
+        }
+    }
+
+    #[test]
+    fn crease_angle_splits_normals() {
+        let options = LoadOptions {
+            generate_normals: false,
+            ..LoadOptions::default()
+        };
+        let obj = format!("{}f 1 2 3\nf 2 1 4\n", FOLD);
+        let mut mesh = read_obj(&obj, &options).unwrap().0;
+
+        // 90° 未満なら折り目で分割
+        mesh.recompute_normals(std::f32::consts::FRAC_PI_4);
+        let indexes = normal_indexes(&mesh);
+        assert_ne!(indexes[0][0], indexes[1][1]);
+        assert_ne!(indexes[0][1], indexes[1][0]);
+        assert_near(&mesh.normals[indexes[0][0] as usize], 0.0, 0.0, 1.0);
+        assert_near(&mesh.normals[indexes[1][1] as usize], 0.0, -1.0, 0.0);
+        assert_eq!(mesh.normals.len(), 2);
+
+        // π なら全て平均
+        mesh.recompute_normals(std::f32::consts::PI);
+        let indexes = normal_indexes(&mesh);
+        assert_eq!(indexes[0][0], indexes[1][1]);
+        assert_eq!(indexes[0][1], indexes[1][0]);
+        let h = 0.5f32.sqrt();
+        assert_near(&mesh.normals[indexes[0][0] as usize], 0.0, -h, h);
+    }
+
+    #[test]
+    fn recompute_normals_drops_tangents() {
+        let obj = "v 0 0 0\nv 1 0 0\nv 1 1 0\nvt 0 0\nvt 1 0\nvt 1 1\nf 1/1 2/2 3/3\n";
+        let mut mesh = read_obj(obj, &LoadOptions::default()).unwrap().0;
+        mesh.generate_tangents();
+        assert!(!mesh.tangents.is_empty());
+
+        mesh.recompute_normals(0.5);
+        assert!(mesh.tangents.is_empty());
+        let face = &mesh.objects[0].groups[0].surfaces[0].faces[0];
+        assert!(face.points.iter().all(|p| p.tangent_index == -1));
+    }
+}
@@ -0,0 +1,184 @@
+use super::{get, Mesh, Vecter3D, Vecter4D};
+use num_traits::{Float, FromPrimitive, ToPrimitive};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+// 集計中の (接線, 従法線, 面法線)
+type TangentSum<T> = (Vecter3D<T>, Vecter3D<T>, Vecter3D<T>);
+
+// 三角形の各頂点の角度(ラジアン)
+fn triangle_angles<T: Float + FromPrimitive>(pos: &[Vecter3D<T>; 3]) -> [T; 3] {
+    [0, 1, 2].map(|k| {
+        let u = pos[(k + 2) % 3].sub(&pos[k]).normalize();
+        let v = pos[(k + 1) % 3].sub(&pos[k]).normalize();
+        u.dot(&v).max(-T::one()).min(T::one()).acos()
+    })
+}
+
+#[allow(dead_code)]
+impl<T: FromStr + Float + FromPrimitive + ToPrimitive> Mesh<T> {
+    // 接線生成
+    //  - (頂点, テクスチャ座標, 法線) の組毎に接線を求める
+    //  - 三角形毎の接線をその三角形での角度で重み付けして平均し、法線に対して直交化
+    //  - w は従法線の向き (bitangent = w * cross(normal, tangent))
+    //    ファイルのテクスチャ座標(v 上向き)で求めるので flip_v には影響されない
+    //  テクスチャ座標の無い点には接線を付けない
+    pub fn generate_tangents(&mut self) {
+        type Key = (i32, i32, i32);
+        let mut sums: HashMap<Key, TangentSum<T>> = HashMap::new();
+
+        // 面毎の接線/従法線を集計
+        for obj in &self.objects {
+            for grp in &obj.groups {
+                for surf in &grp.surfaces {
+                    for face in &surf.faces {
+                        let face_normal = face.area_normal(&self.vertexes).normalize();
+                        for triangle in face.triangulate(&self.vertexes) {
+                            let points = triangle.map(|i| &face.points[i]);
+                            if points.iter().any(|p| {
+                                p.texture_coordinate_index < 0
+                                    || p.texture_coordinate_index as usize
+                                        >= self.texture_coordinates.len()
+                                    || p.vertex_index < 0
+                                    || p.vertex_index as usize >= self.vertexes.len()
+                            }) {
+                                continue;
+                            }
+                            let pos = points.map(|p| self.vertexes[p.vertex_index as usize]);
+                            let uv = points.map(|p| {
+                                self.texture_coordinates[p.texture_coordinate_index as usize]
+                            });
+
+                            let e1 = pos[1].sub(&pos[0]);
+                            let e2 = pos[2].sub(&pos[0]);
+                            let (du1, dv1) = (uv[1].u - uv[0].u, uv[1].v - uv[0].v);
+                            let (du2, dv2) = (uv[2].u - uv[0].u, uv[2].v - uv[0].v);
+                            let det = du1 * dv2 - du2 * dv1;
+                            if det.abs() <= T::epsilon() {
+                                continue; // テクスチャ座標が縮退
+                            }
+                            let angles = triangle_angles(&pos);
+                            let r = T::one() / det;
+                            let tangent = e1.scale(dv2).sub(&e2.scale(dv1)).scale(r).normalize();
+                            let bitangent = e2.scale(du1).sub(&e1.scale(du2)).scale(r).normalize();
+
+                            for (k, point) in points.iter().enumerate() {
+                                let key = (
+                                    point.vertex_index,
+                                    point.texture_coordinate_index,
+                                    point.normal_index,
+                                );
+                                let angle = angles[k];
+                                let sum = sums.entry(key).or_insert((
+                                    Vecter3D::new(),
+                                    Vecter3D::new(),
+                                    face_normal,
+                                ));
+                                sum.0 = sum.0.add(&tangent.scale(angle));
+                                sum.1 = sum.1.add(&bitangent.scale(angle));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // 直交化して登録
+        let mut tangents = Vec::new();
+        let mut tangent_index: HashMap<Key, i32> = HashMap::new();
+        let mut keys: Vec<Key> = sums.keys().copied().collect();
+        keys.sort_unstable();
+        for key in keys {
+            let (tangent, bitangent, face_normal) = &sums[&key];
+            let normal = if key.2 >= 0 && (key.2 as usize) < self.normals.len() {
+                self.normals[key.2 as usize].normalize()
+            } else {
+                *face_normal
+            };
+            let t = tangent.sub(&normal.scale(normal.dot(tangent))).normalize();
+            let w = if normal.cross(&t).dot(bitangent) < T::zero() {
+                -T::one()
+            } else {
+                T::one()
+            };
+            tangent_index.insert(key, tangents.len() as i32);
+            tangents.push(Vecter4D {
+                x: t.x,
+                y: t.y,
+                z: t.z,
+                w,
+            });
+        }
+
+        // 接線インデックスの付け替え
+        for obj in &mut self.objects {
+            for grp in &mut obj.groups {
+                for surf in &mut grp.surfaces {
+                    for face in &mut surf.faces {
+                        for point in &mut face.points {
+                            let key = (
+                                point.vertex_index,
+                                point.texture_coordinate_index,
+                                point.normal_index,
+                            );
+                            point.tangent_index = *tangent_index.get(&key).unwrap_or(&-1);
+                        }
+                    }
+                }
+            }
+        }
+        self.tangents = tangents;
+    }
+
+    // 接線付き頂点配列(座標3, 法線3, テクスチャ座標2, 接線4)
+    //  並びは get_vertex_array と同じ、事前に generate_tangents を呼んでおくこと
+    pub fn get_vertex_array_with_tangent(&self) -> Vec<T> {
+        let mut buffer = Vec::<T>::new();
+        for (_, triangles) in self.material_triangles() {
+            for point in triangles.iter().flatten() {
+                self.push_point(&mut buffer, point);
+                let tangent = if point.tangent_index >= 0 {
+                    self.tangents.get(point.tangent_index as usize)
+                } else {
+                    None
+                };
+                if let Some(tangent) = tangent {
+                    buffer.push(tangent.x);
+                    buffer.push(tangent.y);
+                    buffer.push(tangent.z);
+                    buffer.push(tangent.w);
+                } else {
+                    buffer.push(get::<T>(0.0));
+                    buffer.push(get::<T>(0.0));
+                    buffer.push(get::<T>(0.0));
+                    buffer.push(get::<T>(1.0));
+                }
+            }
+        }
+        buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::read_obj;
+    use super::super::LoadOptions;
+
+    #[test]
+    fn quad_tangent_follows_u() {
+        let obj = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\nvn 0 0 1\nf 1/1/1 2/2/1 3/3/1 4/4/1\n";
+        for flip_v in [true, false] {
+            let options = LoadOptions {
+                flip_v,
+                ..LoadOptions::default()
+            };
+            let mut mesh = read_obj(obj, &options).unwrap().0;
+            mesh.generate_tangents();
+            assert_eq!(mesh.tangents.len(), 4);
+            for t in &mesh.tangents {
+                assert!((t.x - 1.0).abs() < 1.0e-6 && t.y.abs() < 1.0e-6);
+                assert_eq!(t.w, 1.0);
+            }
+        }
+    }
+}
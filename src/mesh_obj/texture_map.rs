@@ -0,0 +1,155 @@
+use super::{get, LineContext, MeshError, Vecter3D};
+use num_traits::FromPrimitive;
+use std::str::FromStr;
+
+// テクスチャマップ(ファイル名とオプション)
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextureMap<T: FromPrimitive> {
+    pub filename: String,
+    pub scale: Vecter3D<T>,      // -s u v w
+    pub offset: Vecter3D<T>,     // -o u v w
+    pub turbulence: Vecter3D<T>, // -t u v w
+    pub clamp: bool,             // -clamp on/off
+    pub blend_u: bool,           // -blendu on/off
+    pub blend_v: bool,           // -blendv on/off
+    pub color_correction: bool,  // -cc on/off
+    pub bump_multiplier: T,      // -bm mult
+    pub channel: String,         // -imfchan r/g/b/m/l/z(空なら未指定)
+    pub boost: T,                // -boost value
+    pub brightness: T,           // -mm base gain の base
+    pub contrast: T,             // -mm base gain の gain
+    pub resolution: i32,         // -texres value(0 なら未指定)
+}
+
+#[allow(dead_code)]
+impl<T: FromPrimitive> TextureMap<T> {
+    pub fn new() -> Self {
+        TextureMap::<T> {
+            filename: String::new(),
+            scale: Vecter3D {
+                x: get::<T>(1.0),
+                y: get::<T>(1.0),
+                z: get::<T>(1.0),
+            },
+            offset: Vecter3D::<T>::new(),
+            turbulence: Vecter3D::<T>::new(),
+            clamp: false,
+            blend_u: true,
+            blend_v: true,
+            color_correction: false,
+            bump_multiplier: get::<T>(1.0),
+            channel: String::new(),
+            boost: get::<T>(0.0),
+            brightness: get::<T>(0.0),
+            contrast: get::<T>(1.0),
+            resolution: 0,
+        }
+    }
+
+    pub fn empty(&self) -> bool {
+        self.filename.is_empty()
+    }
+}
+
+impl<T: FromPrimitive> Default for TextureMap<T> {
+    fn default() -> Self {
+        TextureMap::new()
+    }
+}
+
+#[allow(dead_code)]
+impl<T: FromStr + FromPrimitive + Copy> TextureMap<T> {
+    // map_Kd 等の引数を解析(オプションの後の残りがファイル名)
+    pub(super) fn parse(ctx: &LineContext, args: &[&str]) -> Result<Self, MeshError> {
+        let mut map = TextureMap::<T>::new();
+        let mut i = 0;
+        while i < args.len() && args[i].starts_with('-') {
+            let option = args[i];
+            i += 1;
+            match option {
+                "-s" => i += parse_uvw(ctx, option, &args[i..], &mut map.scale)?,
+                "-o" => i += parse_uvw(ctx, option, &args[i..], &mut map.offset)?,
+                "-t" => i += parse_uvw(ctx, option, &args[i..], &mut map.turbulence)?,
+                "-clamp" => map.clamp = parse_switch(ctx, option, args.get(i))?,
+                "-blendu" => map.blend_u = parse_switch(ctx, option, args.get(i))?,
+                "-blendv" => map.blend_v = parse_switch(ctx, option, args.get(i))?,
+                "-cc" => map.color_correction = parse_switch(ctx, option, args.get(i))?,
+                "-bm" => map.bump_multiplier = parse_value(ctx, option, args.get(i))?,
+                "-boost" => map.boost = parse_value(ctx, option, args.get(i))?,
+                "-texres" => map.resolution = parse_value(ctx, option, args.get(i))?,
+                "-imfchan" => match args.get(i) {
+                    Some(&c) if ["r", "g", "b", "m", "l", "z"].contains(&c) => {
+                        map.channel = c.to_string()
+                    }
+                    Some(c) => return Err(ctx.syntax_error(c)),
+                    None => return Err(ctx.syntax_error(option)),
+                },
+                "-mm" => {
+                    map.brightness = parse_value(ctx, option, args.get(i))?;
+                    i += 1;
+                    map.contrast = parse_value(ctx, option, args.get(i))?;
+                }
+                _ => return Err(ctx.syntax_error(option)),
+            }
+            // -s/-o/-t 以外は値を1つ消費
+            if !matches!(option, "-s" | "-o" | "-t") {
+                i += 1;
+            }
+        }
+
+        // ファイル名(空白を含む場合があるので残りを連結)
+        if i >= args.len() {
+            return Err(ctx.syntax_error(args.last().copied().unwrap_or("")));
+        }
+        map.filename = args[i..].join(" ");
+        Ok(map)
+    }
+}
+
+// 1つの値を解析(値が無ければオプション名の位置でエラー)
+fn parse_value<V: FromStr>(
+    ctx: &LineContext,
+    option: &str,
+    arg: Option<&&str>,
+) -> Result<V, MeshError> {
+    match arg {
+        Some(arg) => ctx.parse_number::<V>(arg),
+        None => Err(ctx.syntax_error(option)),
+    }
+}
+
+// on/off の解析
+fn parse_switch(ctx: &LineContext, option: &str, arg: Option<&&str>) -> Result<bool, MeshError> {
+    match arg {
+        Some(&"on") => Ok(true),
+        Some(&"off") => Ok(false),
+        Some(arg) => Err(ctx.syntax_error(arg)),
+        None => Err(ctx.syntax_error(option)),
+    }
+}
+
+// u [v [w]] の解析(省略した成分はそのまま、消費した個数を返す)
+fn parse_uvw<T: FromStr + FromPrimitive + Copy>(
+    ctx: &LineContext,
+    option: &str,
+    args: &[&str],
+    value: &mut Vecter3D<T>,
+) -> Result<usize, MeshError> {
+    let values: Vec<T> = args
+        .iter()
+        .take(3)
+        .map_while(|arg| arg.parse::<T>().ok())
+        .collect();
+    if values.is_empty() {
+        return Err(ctx.syntax_error(args.first().copied().unwrap_or(option)));
+    }
+    value.x = values[0];
+    if let Some(&v) = values.get(1) {
+        value.y = v;
+    }
+    if let Some(&w) = values.get(2) {
+        value.z = w;
+    }
+    Ok(values.len())
+}
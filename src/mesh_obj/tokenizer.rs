@@ -0,0 +1,171 @@
+use std::io;
+use std::io::prelude::*;
+
+// トークン(位置は元ファイルの行/桁、共に1始まり)
+pub(super) struct Token {
+    pub text: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+// 論理行(行継続を結合したもの)
+pub(super) struct LogicalLine {
+    pub line: usize,
+    pub tokens: Vec<Token>,
+}
+
+// OBJ/MTL 共通の字句解析
+//  - 任意の空白(タブ, 連続空白, 行末の \r)で分割
+//  - 行末の '\' で次の行に継続
+//  - '#' 以降はコメント
+pub(super) struct Tokenizer<R: BufRead> {
+    lines: io::Lines<R>,
+    line_num: usize,
+}
+
+impl<R: BufRead> Tokenizer<R> {
+    pub fn new(reader: R) -> Self {
+        Tokenizer {
+            lines: reader.lines(),
+            line_num: 0,
+        }
+    }
+
+    // 最後に読んだ物理行の番号(1始まり)
+    pub fn line_num(&self) -> usize {
+        self.line_num
+    }
+}
+
+// 1行分を分解してトークンを追加し、継続行なら true を返す
+fn split_line(text: &str, line: usize, tokens: &mut Vec<Token>) -> bool {
+    // コメント除去
+    let text = match text.find('#') {
+        Some(pos) => &text[..pos],
+        None => text,
+    };
+
+    // 行継続判定
+    let trimmed = text.trim_end();
+    let (text, continued) = match trimmed.strip_suffix('\\') {
+        Some(body) => (body, true),
+        None => (trimmed, false),
+    };
+
+    let mut start: Option<(usize, usize)> = None; // (バイト位置, 桁)
+    for (column, (pos, c)) in text.char_indices().enumerate() {
+        if c.is_whitespace() {
+            if let Some((begin, begin_column)) = start.take() {
+                tokens.push(Token {
+                    text: text[begin..pos].to_string(),
+                    line,
+                    column: begin_column,
+                });
+            }
+        } else if start.is_none() {
+            start = Some((pos, column + 1));
+        }
+    }
+    if let Some((begin, begin_column)) = start {
+        tokens.push(Token {
+            text: text[begin..].to_string(),
+            line,
+            column: begin_column,
+        });
+    }
+
+    continued
+}
+
+impl<R: BufRead> Iterator for Tokenizer<R> {
+    type Item = io::Result<LogicalLine>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut logical: Option<LogicalLine> = None;
+        loop {
+            let text = match self.lines.next() {
+                Some(Ok(text)) => text,
+                Some(Err(e)) => {
+                    if e.kind() == io::ErrorKind::InvalidData {
+                        self.line_num += 1; // 不正な UTF-8 の行は読み飛ばされている
+                    }
+                    return Some(Err(e));
+                }
+                None => return logical.map(Ok), // 継続中にファイル終端
+            };
+            self.line_num += 1;
+
+            let line = logical.get_or_insert(LogicalLine {
+                line: self.line_num,
+                tokens: Vec::new(),
+            });
+            if !split_line(&text, self.line_num, &mut line.tokens) {
+                return logical.map(Ok);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    // (トークン, 行, 桁) の並び
+    type Tokens = Vec<(String, usize, usize)>;
+
+    // 論理行毎の (行番号, トークン)
+    fn tokenize(text: &str) -> Vec<(usize, Tokens)> {
+        Tokenizer::new(Cursor::new(text))
+            .map(|line| {
+                let line = line.unwrap();
+                let tokens = line
+                    .tokens
+                    .into_iter()
+                    .map(|t| (t.text, t.line, t.column))
+                    .collect();
+                (line.line, tokens)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn whitespace_and_comments() {
+        let lines = tokenize("v\t1  2 3\r\n# comment\nf 1 2 3 # tail\n");
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[0].1,
+            vec![
+                ("v".to_string(), 1, 1),
+                ("1".to_string(), 1, 3),
+                ("2".to_string(), 1, 6),
+                ("3".to_string(), 1, 8),
+            ]
+        );
+        assert!(lines[1].1.is_empty());
+        assert_eq!(lines[2].1.len(), 4);
+    }
+
+    #[test]
+    fn continuation_lines() {
+        let lines = tokenize("f 1 2 \\\n  3 4\nv 0 0 0\nf 1 \\");
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].0, 1);
+        assert_eq!(lines[0].1.len(), 5);
+        assert_eq!(lines[0].1[3], ("3".to_string(), 2, 3));
+        assert_eq!(lines[1].0, 3);
+        // ファイル終端で継続中でも行として返す
+        assert_eq!(lines[2].0, 4);
+        assert_eq!(lines[2].1.len(), 2);
+    }
+
+    #[test]
+    fn invalid_utf8_advances_line_number() {
+        let mut tokenizer = Tokenizer::new(Cursor::new(&b"v 0\n\xff\nv 1\n"[..]));
+        assert!(tokenizer.next().unwrap().is_ok());
+        let error = tokenizer.next().unwrap().err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(tokenizer.line_num(), 2);
+        assert_eq!(tokenizer.next().unwrap().unwrap().line, 3);
+    }
+}
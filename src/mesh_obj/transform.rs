@@ -0,0 +1,203 @@
+use super::{get, Mesh, Vecter3D};
+use num_traits::{Float, FromPrimitive};
+
+// 4x4 行列(列優先 matrix[列][行]、cgmath::Matrix4 から .into() で作れる)
+pub type Matrix<T> = [[T; 4]; 4];
+
+// 単位行列
+fn identity<T: Float>() -> Matrix<T> {
+    let (o, l) = (T::zero(), T::one());
+    [[l, o, o, o], [o, l, o, o], [o, o, l, o], [o, o, o, l]]
+}
+
+// 点の変換(w で割る)
+fn transform_point<T: Float + FromPrimitive>(m: &Matrix<T>, v: &Vecter3D<T>) -> Vecter3D<T> {
+    let w = m[0][3] * v.x + m[1][3] * v.y + m[2][3] * v.z + m[3][3];
+    let w = if w == T::zero() { T::one() } else { w };
+    Vecter3D {
+        x: (m[0][0] * v.x + m[1][0] * v.y + m[2][0] * v.z + m[3][0]) / w,
+        y: (m[0][1] * v.x + m[1][1] * v.y + m[2][1] * v.z + m[3][1]) / w,
+        z: (m[0][2] * v.x + m[1][2] * v.y + m[2][2] * v.z + m[3][2]) / w,
+    }
+}
+
+// 方向の変換(左上 3x3 のみ)
+fn transform_vector<T: Float + FromPrimitive>(m: &Matrix<T>, v: &Vecter3D<T>) -> Vecter3D<T> {
+    Vecter3D {
+        x: m[0][0] * v.x + m[1][0] * v.y + m[2][0] * v.z,
+        y: m[0][1] * v.x + m[1][1] * v.y + m[2][1] * v.z,
+        z: m[0][2] * v.x + m[1][2] * v.y + m[2][2] * v.z,
+    }
+}
+
+// 左上 3x3 の余因子行列(= 逆転置行列 * 行列式)と行列式
+fn cofactor<T: Float>(m: &Matrix<T>) -> (Matrix<T>, T) {
+    let a = |c: usize, r: usize| m[c][r];
+    let mut out = identity();
+    for (c, column) in out.iter_mut().take(3).enumerate() {
+        for (r, value) in column.iter_mut().take(3).enumerate() {
+            let (c1, c2) = ((c + 1) % 3, (c + 2) % 3);
+            let (r1, r2) = ((r + 1) % 3, (r + 2) % 3);
+            *value = a(c1, r1) * a(c2, r2) - a(c2, r1) * a(c1, r2);
+        }
+    }
+    let det = a(0, 0) * out[0][0] + a(1, 0) * out[1][0] + a(2, 0) * out[2][0];
+    (out, det)
+}
+
+#[allow(dead_code)]
+impl<T: Float + FromPrimitive> Mesh<T> {
+    // 行列による変換
+    //  - 頂点は行列、法線は逆転置行列で変換
+    //  - 鏡像変換(行列式が負)なら面の向きと接線の w を反転して表裏を保つ
+    pub fn transform(&mut self, matrix: &Matrix<T>) {
+        let (normal_matrix, det) = cofactor(matrix);
+        let mirror = det < T::zero();
+
+        for v in &mut self.vertexes {
+            *v = transform_point(matrix, v);
+        }
+        for n in &mut self.normals {
+            let normal = transform_vector(&normal_matrix, n).normalize();
+            *n = if mirror {
+                normal.scale(-T::one())
+            } else {
+                normal
+            };
+        }
+        for t in &mut self.tangents {
+            let v = Vecter3D {
+                x: t.x,
+                y: t.y,
+                z: t.z,
+            };
+            let v = transform_vector(matrix, &v).normalize();
+            t.x = v.x;
+            t.y = v.y;
+            t.z = v.z;
+            if mirror {
+                t.w = -t.w;
+            }
+        }
+
+        if mirror {
+            for obj in &mut self.objects {
+                for grp in &mut obj.groups {
+                    for surf in &mut grp.surfaces {
+                        for face in &mut surf.faces {
+                            face.points.reverse();
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // 平行移動
+    pub fn translate(&mut self, x: T, y: T, z: T) {
+        let mut m = identity();
+        m[3][0] = x;
+        m[3][1] = y;
+        m[3][2] = z;
+        self.transform(&m);
+    }
+
+    // 回転(axis 周りに angle ラジアン、右手系)
+    pub fn rotate(&mut self, axis: &Vecter3D<T>, angle: T) {
+        let a = axis.normalize();
+        let (s, c) = angle.sin_cos();
+        let t = T::one() - c;
+        let mut m = identity();
+        m[0][0] = t * a.x * a.x + c;
+        m[0][1] = t * a.x * a.y + s * a.z;
+        m[0][2] = t * a.x * a.z - s * a.y;
+        m[1][0] = t * a.x * a.y - s * a.z;
+        m[1][1] = t * a.y * a.y + c;
+        m[1][2] = t * a.y * a.z + s * a.x;
+        m[2][0] = t * a.x * a.z + s * a.y;
+        m[2][1] = t * a.y * a.z - s * a.x;
+        m[2][2] = t * a.z * a.z + c;
+        self.transform(&m);
+    }
+
+    // 拡大縮小
+    pub fn scale(&mut self, x: T, y: T, z: T) {
+        let mut m = identity();
+        m[0][0] = x;
+        m[1][1] = y;
+        m[2][2] = z;
+        self.transform(&m);
+    }
+
+    // 境界ボックスの中心を原点に移動(移動量を返す)
+    pub fn recenter(&mut self) -> Vecter3D<T> {
+        let offset = match self.get_bounding_box() {
+            Some(bounds) => bounds.center().scale(-T::one()),
+            None => Vecter3D::new(),
+        };
+        self.translate(offset.x, offset.y, offset.z);
+        offset
+    }
+
+    // 中心を原点に移動し、境界ボックスの最大辺が size になるよう拡大縮小(倍率を返す)
+    pub fn normalize_size(&mut self, size: T) -> T {
+        self.recenter();
+        let extent = match self.get_bounding_box() {
+            Some(bounds) => {
+                let s = bounds.size();
+                s.x.max(s.y).max(s.z)
+            }
+            None => T::zero(),
+        };
+        if extent <= T::zero() {
+            return T::one();
+        }
+        let factor = size / extent;
+        self.scale(factor, factor, factor);
+        factor
+    }
+
+    // 単位サイズ(最大辺 1)に正規化
+    pub fn normalize_unit(&mut self) -> T {
+        self.normalize_size(get::<T>(1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::read_obj;
+    use super::super::LoadOptions;
+
+    #[test]
+    fn mirror_keeps_front_faces() {
+        let obj = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nvt 1 0\nvt 0 1\nvn 0 0 1\n\
+                   f 1/1/1 2/2/1 3/3/1\n";
+        let mut mesh = read_obj(obj, &LoadOptions::default()).unwrap().0;
+        mesh.generate_tangents();
+        let w = mesh.tangents[0].w;
+
+        mesh.scale(1.0, 1.0, -1.0);
+        let face = &mesh.objects[0].groups[0].surfaces[0].faces[0];
+        let order: Vec<i32> = face.points.iter().map(|p| p.vertex_index).collect();
+        assert_eq!(order, vec![2, 1, 0]);
+
+        // 裏返した面の法線とファイルの法線(逆転置で変換)が同じ向き
+        let normal = mesh.normals[0];
+        assert_eq!((normal.x, normal.y, normal.z), (0.0, 0.0, -1.0));
+        assert!(face.area_normal(&mesh.vertexes).normalize().dot(&normal) > 0.999);
+        assert!(mesh.tangents.iter().all(|t| t.w == -w));
+    }
+
+    #[test]
+    fn normalize_size_fits_extent() {
+        let obj = "v -1 0 0\nv 3 2 0\nv 0 0 1\nf 1 2 3\n";
+        let mut mesh = read_obj(obj, &LoadOptions::default()).unwrap().0;
+        let factor = mesh.normalize_size(2.0);
+        assert_eq!(factor, 0.5);
+
+        let bounds = mesh.get_bounding_box().unwrap();
+        let size = bounds.size();
+        assert!((size.x.max(size.y).max(size.z) - 2.0).abs() < 1.0e-6);
+        assert!(bounds.center().length() < 1.0e-6);
+    }
+}
@@ -0,0 +1,147 @@
+use super::{Face, Vecter3D};
+use num_traits::{Float, FromPrimitive};
+
+// 2D座標
+#[derive(Clone, Copy)]
+struct Point2D<T> {
+    x: T,
+    y: T,
+}
+
+// 外積(2D)
+fn cross<T: Float>(o: Point2D<T>, a: Point2D<T>, b: Point2D<T>) -> T {
+    (a.x - o.x) * (b.y - o.y) - (a.y - o.y) * (b.x - o.x)
+}
+
+// 三角形内判定(境界上も内側扱い)
+fn in_triangle<T: Float>(p: Point2D<T>, a: Point2D<T>, b: Point2D<T>, c: Point2D<T>) -> bool {
+    let zero = T::zero();
+    cross(a, b, p) >= zero && cross(b, c, p) >= zero && cross(c, a, p) >= zero
+}
+
+// 扇形分割
+fn fan(n: usize) -> Vec<[usize; 3]> {
+    (1..n - 1).map(|i| [0, i, i + 1]).collect()
+}
+
+#[allow(dead_code)]
+impl Face {
+    // 三角形分割(face.points のインデックスの組を返す)
+    pub(super) fn triangulate<T: Float + FromPrimitive>(
+        &self,
+        vertexes: &[Vecter3D<T>],
+    ) -> Vec<[usize; 3]> {
+        let n = self.points.len();
+        if n < 3 {
+            return Vec::new();
+        }
+        if n == 3 {
+            return vec![[0, 1, 2]];
+        }
+
+        // 頂点座標取得(不正インデックスなら扇形で妥協)
+        let mut positions = Vec::with_capacity(n);
+        for point in &self.points {
+            if point.vertex_index < 0 || point.vertex_index as usize >= vertexes.len() {
+                return fan(n);
+            }
+            positions.push(&vertexes[point.vertex_index as usize]);
+        }
+
+        // 法線の最大成分の軸を落として平面に投影
+        let zero = T::zero();
+        let normal = self.area_normal(vertexes);
+        let (ax, ay, az) = (normal.x.abs(), normal.y.abs(), normal.z.abs());
+        if ax.max(ay).max(az) <= T::epsilon() {
+            return fan(n); // 縮退面
+        }
+        let projected: Vec<Point2D<T>> = positions
+            .iter()
+            .map(|v| {
+                if ax >= ay && ax >= az {
+                    Point2D { x: v.y, y: v.z }
+                } else if ay >= az {
+                    Point2D { x: v.z, y: v.x }
+                } else {
+                    Point2D { x: v.x, y: v.y }
+                }
+            })
+            .collect();
+
+        // 投影後の向き(反時計回りを正とする)
+        let mut area = zero;
+        for i in 0..n {
+            let a = projected[i];
+            let b = projected[(i + 1) % n];
+            area = area + (a.x * b.y - b.x * a.y);
+        }
+        let sign = if area < zero { -T::one() } else { T::one() };
+        let oriented = |o: Point2D<T>, a: Point2D<T>, b: Point2D<T>| cross(o, a, b) * sign;
+
+        // 凸多角形なら扇形分割
+        let convex = (0..n).all(|i| {
+            oriented(projected[i], projected[(i + 1) % n], projected[(i + 2) % n]) >= zero
+        });
+        if convex {
+            return fan(n);
+        }
+
+        // 凹多角形は耳切り法
+        let mut remain: Vec<usize> = (0..n).collect();
+        let mut triangles = Vec::with_capacity(n - 2);
+        while remain.len() > 3 {
+            let m = remain.len();
+            let mut ear = None;
+            for i in 0..m {
+                let prev = remain[(i + m - 1) % m];
+                let cur = remain[i];
+                let next = remain[(i + 1) % m];
+                let (a, b, c) = (projected[prev], projected[cur], projected[next]);
+                if oriented(a, b, c) <= zero {
+                    continue; // 凹頂点
+                }
+                let (a, b, c) = if sign < zero { (c, b, a) } else { (a, b, c) };
+                let blocked = remain.iter().any(|&j| {
+                    j != prev && j != cur && j != next && in_triangle(projected[j], a, b, c)
+                });
+                if !blocked {
+                    ear = Some(i);
+                    break;
+                }
+            }
+
+            // 数値誤差等で耳が見つからない場合は先頭を切る
+            let i = ear.unwrap_or(0);
+            triangles.push([remain[(i + m - 1) % m], remain[i], remain[(i + 1) % m]]);
+            remain.remove(i);
+        }
+        triangles.push([remain[0], remain[1], remain[2]]);
+
+        triangles
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::read_obj;
+    use super::super::LoadOptions;
+
+    #[test]
+    fn concave_face_is_ear_clipped() {
+        // (0,0) から扇形に分割すると (0,0) (4,4) (2,1) が裏返る
+        let obj = "v 0 0 0\nv 4 0 0\nv 4 4 0\nv 2 1 0\nv 0 4 0\nf 1 2 3 4 5\n";
+        let mesh = read_obj(obj, &LoadOptions::default()).unwrap().0;
+        let face = &mesh.objects[0].groups[0].surfaces[0].faces[0];
+        let triangles = face.triangulate(&mesh.vertexes);
+        assert_eq!(triangles.len(), 3);
+
+        let mut area = 0.0;
+        for triangle in triangles {
+            let [a, b, c] = triangle.map(|i| mesh.vertexes[face.points[i].vertex_index as usize]);
+            let signed = ((b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)) * 0.5;
+            assert!(signed > 0.0);
+            area += signed;
+        }
+        assert_eq!(area, 10.0);
+    }
+}
@@ -0,0 +1,329 @@
+use super::{Face, Mesh, Vecter3D};
+use num_traits::{Float, FromPrimitive};
+use std::collections::HashMap;
+
+// 面の位置(オブジェクト, グループ, サーフェース, 面 の番号)
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FaceLocation {
+    pub object_index: usize,
+    pub group_index: usize,
+    pub surface_index: usize,
+    pub face_index: usize,
+}
+
+// インデックスの種類
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexKind {
+    Vertex,
+    Normal,
+    TextureCoordinate,
+}
+
+// 範囲外のインデックス
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexError {
+    pub location: FaceLocation,
+    pub point_index: usize, // 面の中の点の番号
+    pub kind: IndexKind,
+    pub index: i32,
+}
+
+// 検証結果
+#[allow(dead_code)]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValidationReport {
+    // 範囲外のインデックス
+    pub invalid_indices: Vec<IndexError>,
+    // 3点未満 or 同じ頂点を複数回使う面
+    pub degenerate_faces: Vec<FaceLocation>,
+    // 面積0の面
+    pub zero_area_faces: Vec<FaceLocation>,
+    // 頂点の並びが同じ面(回転は同一視、裏向きは別の面) (重複した面, 最初の面)
+    pub duplicate_faces: Vec<(FaceLocation, FaceLocation)>,
+    // NaN/無限大を含む頂点座標/法線/テクスチャ座標の番号
+    pub non_finite_vertexes: Vec<usize>,
+    pub non_finite_normals: Vec<usize>,
+    pub non_finite_texture_coordinates: Vec<usize>,
+    // どの面からも参照されない頂点
+    pub unused_vertexes: Vec<usize>,
+    // 3面以上で共有される辺(頂点番号の小さい順)
+    pub non_manifold_edges: Vec<(i32, i32)>,
+    // 隣接面で同じ向きに使われる辺(面の向きの不一致)
+    pub inconsistent_edges: Vec<(i32, i32)>,
+}
+
+#[allow(dead_code)]
+impl ValidationReport {
+    // 問題が無ければ true
+    pub fn is_valid(&self) -> bool {
+        *self == ValidationReport::default()
+    }
+}
+
+#[allow(dead_code)]
+impl Face {
+    // 重複判定用の頂点の並び(最小の頂点から始まるよう回転、向きは保つ)
+    //  f 1 2 3 と f 2 3 1 は同じ、裏向きの f 3 2 1 は別の面として扱う
+    pub(super) fn winding_key(&self) -> Vec<i32> {
+        let mut key: Vec<i32> = self.points.iter().map(|p| p.vertex_index).collect();
+        if let Some(start) = (0..key.len()).min_by_key(|&i| key[i]) {
+            key.rotate_left(start);
+        }
+        key
+    }
+
+    // 面積0の面か(周長に対して面積が極端に小さければ0とみなす)
+    pub(super) fn is_zero_area<T: Float + FromPrimitive>(&self, vertexes: &[Vecter3D<T>]) -> bool {
+        let n = self.points.len();
+        let mut perimeter = T::zero();
+        for i in 0..n {
+            let a = vertexes.get(self.points[i].vertex_index as usize);
+            let b = vertexes.get(self.points[(i + 1) % n].vertex_index as usize);
+            if let (Some(a), Some(b)) = (a, b) {
+                perimeter = perimeter + b.sub(a).length();
+            }
+        }
+        self.area_normal(vertexes).length() <= T::epsilon() * perimeter * perimeter
+    }
+}
+
+#[allow(dead_code)]
+impl<T: Float + FromPrimitive> Mesh<T> {
+    // メッシュの検証
+    pub fn validate(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+
+        // 数値の検査
+        let finite3 = |x: T, y: T, z: T| x.is_finite() && y.is_finite() && z.is_finite();
+        for (i, v) in self.vertexes.iter().enumerate() {
+            if !finite3(v.x, v.y, v.z) {
+                report.non_finite_vertexes.push(i);
+            }
+        }
+        for (i, n) in self.normals.iter().enumerate() {
+            if !finite3(n.x, n.y, n.z) {
+                report.non_finite_normals.push(i);
+            }
+        }
+        for (i, vt) in self.texture_coordinates.iter().enumerate() {
+            if !vt.u.is_finite() || !vt.v.is_finite() {
+                report.non_finite_texture_coordinates.push(i);
+            }
+        }
+
+        // 面の検査
+        let mut used = vec![false; self.vertexes.len()];
+        let mut first_face: HashMap<Vec<i32>, FaceLocation> = HashMap::new();
+        let mut edges: HashMap<(i32, i32), usize> = HashMap::new(); // 向き付きの辺の使用数
+        for (object_index, obj) in self.objects.iter().enumerate() {
+            for (group_index, grp) in obj.groups.iter().enumerate() {
+                for (surface_index, surf) in grp.surfaces.iter().enumerate() {
+                    for (face_index, face) in surf.faces.iter().enumerate() {
+                        let location = FaceLocation {
+                            object_index,
+                            group_index,
+                            surface_index,
+                            face_index,
+                        };
+                        if !self.check_indices(face, location, &mut report) {
+                            continue;
+                        }
+                        for point in &face.points {
+                            used[point.vertex_index as usize] = true;
+                        }
+
+                        // 縮退面
+                        let mut indexes: Vec<i32> =
+                            face.points.iter().map(|p| p.vertex_index).collect();
+                        indexes.sort_unstable();
+                        let repeated = indexes.windows(2).any(|w| w[0] == w[1]);
+                        if indexes.len() < 3 || repeated {
+                            report.degenerate_faces.push(location);
+                            continue;
+                        }
+                        if face.is_zero_area(&self.vertexes) {
+                            report.zero_area_faces.push(location);
+                        }
+
+                        // 重複面(頂点の並びが同じ面)
+                        let key = face.winding_key();
+                        match first_face.get(&key) {
+                            Some(first) => report.duplicate_faces.push((location, *first)),
+                            None => {
+                                first_face.insert(key, location);
+                            }
+                        }
+
+                        // 辺
+                        let n = face.points.len();
+                        for i in 0..n {
+                            let a = face.points[i].vertex_index;
+                            let b = face.points[(i + 1) % n].vertex_index;
+                            *edges.entry((a, b)).or_insert(0) += 1;
+                        }
+                    }
+                }
+            }
+        }
+
+        report.unused_vertexes = (0..used.len()).filter(|&i| !used[i]).collect();
+
+        // 辺の共有数と向きの検査
+        let mut shared: HashMap<(i32, i32), usize> = HashMap::new();
+        for (&(a, b), &count) in &edges {
+            *shared.entry((a.min(b), a.max(b))).or_insert(0) += count;
+            if count > 1 {
+                // 向きの揃った隣接面なら辺は逆向きに1回ずつ使われる
+                report.inconsistent_edges.push((a.min(b), a.max(b)));
+            }
+        }
+        report.non_manifold_edges = shared
+            .into_iter()
+            .filter(|&(_, count)| count > 2)
+            .map(|(edge, _)| edge)
+            .collect();
+        report.non_manifold_edges.sort_unstable();
+        report.inconsistent_edges.sort_unstable();
+        report.inconsistent_edges.dedup();
+
+        report
+    }
+
+    // 面のインデックスの範囲検査(全て範囲内なら true)
+    fn check_indices(
+        &self,
+        face: &Face,
+        location: FaceLocation,
+        report: &mut ValidationReport,
+    ) -> bool {
+        let mut valid = true;
+        for (point_index, point) in face.points.iter().enumerate() {
+            let checks = [
+                (
+                    IndexKind::Vertex,
+                    point.vertex_index,
+                    self.vertexes.len(),
+                    false,
+                ),
+                (
+                    IndexKind::Normal,
+                    point.normal_index,
+                    self.normals.len(),
+                    true,
+                ),
+                (
+                    IndexKind::TextureCoordinate,
+                    point.texture_coordinate_index,
+                    self.texture_coordinates.len(),
+                    true,
+                ),
+            ];
+            for (kind, index, count, optional) in checks {
+                let unspecified = optional && index == -1;
+                if !unspecified && (index < 0 || index as usize >= count) {
+                    report.invalid_indices.push(IndexError {
+                        location,
+                        point_index,
+                        kind,
+                        index,
+                    });
+                    valid = false;
+                }
+            }
+        }
+        valid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::read_obj;
+    use super::super::{CleanupOptions, LoadOptions};
+    use super::*;
+
+    fn validate(obj: &str) -> ValidationReport {
+        let options = LoadOptions {
+            generate_normals: false,
+            ..LoadOptions::default()
+        };
+        read_obj(obj, &options).unwrap().0.validate()
+    }
+
+    fn location(face_index: usize) -> FaceLocation {
+        FaceLocation {
+            object_index: 0,
+            group_index: 0,
+            surface_index: 0,
+            face_index,
+        }
+    }
+
+    const QUAD: &str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n";
+
+    #[test]
+    fn valid_mesh() {
+        let report = validate(&format!("{}f 1 2 3\nf 1 3 4\n", QUAD));
+        assert!(report.is_valid());
+    }
+
+    #[test]
+    fn invalid_indices() {
+        let mut mesh = read_obj(&format!("{}f 1 2 3\n", QUAD), &LoadOptions::default())
+            .unwrap()
+            .0;
+        mesh.objects[0].groups[0].surfaces[0].faces[0].points[2].vertex_index = 9;
+        let report = mesh.validate();
+        assert_eq!(
+            report.invalid_indices,
+            vec![IndexError {
+                location: location(0),
+                point_index: 2,
+                kind: IndexKind::Vertex,
+                index: 9,
+            }]
+        );
+    }
+
+    #[test]
+    fn degenerate_and_zero_area_faces() {
+        let obj = format!("{}v 2 0 0\nf 1 2 1\nf 1 2 5\nf 1 2 3\n", QUAD);
+        let report = validate(&obj);
+        assert_eq!(report.degenerate_faces, vec![location(0)]);
+        assert_eq!(report.zero_area_faces, vec![location(1)]);
+    }
+
+    #[test]
+    fn duplicate_faces_keep_winding() {
+        // 回転しただけの面は重複、裏向きや並びの違う面は別の面
+        let obj = format!("{}f 1 2 3\nf 2 3 1\nf 3 2 1\nf 1 2 3 4\nf 1 3 2 4\n", QUAD);
+        let report = validate(&obj);
+        assert_eq!(report.duplicate_faces, vec![(location(1), location(0))]);
+
+        // 裏表の面は cleanup 後に重複として残らない
+        let mut mesh = read_obj(
+            &format!("{}f 1 2 3\nf 3 2 1\n", QUAD),
+            &LoadOptions::default(),
+        )
+        .unwrap()
+        .0;
+        mesh.cleanup(&CleanupOptions::default());
+        assert!(mesh.validate().duplicate_faces.is_empty());
+    }
+
+    #[test]
+    fn non_manifold_and_inconsistent_edges() {
+        // 辺 1-2 を3面で共有
+        let obj = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 0 -1 0\nv 0 0 1\nf 1 2 3\nf 2 1 4\nf 2 1 5\n";
+        let report = validate(obj);
+        assert_eq!(report.non_manifold_edges, vec![(0, 1)]);
+        assert_eq!(report.inconsistent_edges, vec![(0, 1)]);
+
+        // 隣接面の向きが逆
+        let report = validate(&format!("{}f 1 2 3\nf 1 4 3\n", QUAD));
+        assert!(report.non_manifold_edges.is_empty());
+        assert_eq!(report.inconsistent_edges, vec![(0, 2)]);
+    }
+}
@@ -0,0 +1,257 @@
+use super::{
+    BoundingBox, Face, Group, Material, Mesh, Object, Point, Surface, Texture2D, Vecter3D, Vecter4D,
+};
+use num_traits::{Float, FromPrimitive};
+
+// 読み取り専用の参照(オブジェクト → グループ → サーフェース → 面 → 点)
+
+// オブジェクト
+#[allow(dead_code)]
+#[derive(Clone, Copy)]
+pub struct ObjectView<'a, T: FromPrimitive> {
+    mesh: &'a Mesh<T>,
+    object: &'a Object,
+    index: usize,
+}
+
+// グループ
+#[allow(dead_code)]
+#[derive(Clone, Copy)]
+pub struct GroupView<'a, T: FromPrimitive> {
+    mesh: &'a Mesh<T>,
+    group: &'a Group,
+    object_index: usize,
+    index: usize,
+}
+
+// サーフェース(同一マテリアルの面の集まり)
+#[allow(dead_code)]
+#[derive(Clone, Copy)]
+pub struct SurfaceView<'a, T: FromPrimitive> {
+    mesh: &'a Mesh<T>,
+    surface: &'a Surface,
+}
+
+// 面
+#[allow(dead_code)]
+#[derive(Clone, Copy)]
+pub struct FaceView<'a, T: FromPrimitive> {
+    mesh: &'a Mesh<T>,
+    face: &'a Face,
+}
+
+// 点(インデックスと解決済みの座標/法線/テクスチャ座標)
+#[allow(dead_code)]
+#[derive(Clone, Copy)]
+pub struct PointView<'a, T: FromPrimitive> {
+    mesh: &'a Mesh<T>,
+    point: &'a Point,
+}
+
+#[allow(dead_code)]
+impl<T: Float + FromPrimitive> Mesh<T> {
+    pub fn objects(&self) -> impl Iterator<Item = ObjectView<'_, T>> {
+        self.objects
+            .iter()
+            .enumerate()
+            .map(move |(index, object)| ObjectView {
+                mesh: self,
+                object,
+                index,
+            })
+    }
+
+    // 名前でオブジェクトを検索(同名なら最初のもの)
+    pub fn find_object(&self, object_name: &str) -> Option<ObjectView<'_, T>> {
+        self.objects().find(|obj| obj.name() == object_name)
+    }
+
+    // 名前でグループを検索(全オブジェクトから、同名なら最初のもの)
+    pub fn find_group(&self, group_name: &str) -> Option<GroupView<'_, T>> {
+        self.objects().find_map(|obj| obj.find_group(group_name))
+    }
+}
+
+#[allow(dead_code)]
+impl<'a, T: Float + FromPrimitive> ObjectView<'a, T> {
+    pub fn name(&self) -> &'a str {
+        &self.object.object_name
+    }
+
+    // Mesh::get_object_bounding_box 等に渡す番号
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn groups(&self) -> impl Iterator<Item = GroupView<'a, T>> {
+        let (mesh, object_index) = (self.mesh, self.index);
+        self.object
+            .groups
+            .iter()
+            .enumerate()
+            .map(move |(index, group)| GroupView {
+                mesh,
+                group,
+                object_index,
+                index,
+            })
+    }
+
+    pub fn find_group(&self, group_name: &str) -> Option<GroupView<'a, T>> {
+        self.groups().find(|grp| grp.name() == group_name)
+    }
+
+    pub fn faces(&self) -> impl Iterator<Item = FaceView<'a, T>> {
+        self.groups().flat_map(|grp| grp.faces())
+    }
+
+    pub fn bounding_box(&self) -> Option<BoundingBox<T>> {
+        self.mesh.get_object_bounding_box(self.index)
+    }
+}
+
+#[allow(dead_code)]
+impl<'a, T: Float + FromPrimitive> GroupView<'a, T> {
+    pub fn name(&self) -> &'a str {
+        &self.group.group_name
+    }
+
+    // (オブジェクト番号, グループ番号)
+    pub fn index(&self) -> (usize, usize) {
+        (self.object_index, self.index)
+    }
+
+    pub fn surfaces(&self) -> impl Iterator<Item = SurfaceView<'a, T>> {
+        let mesh = self.mesh;
+        self.group
+            .surfaces
+            .iter()
+            .map(move |surface| SurfaceView { mesh, surface })
+    }
+
+    pub fn faces(&self) -> impl Iterator<Item = FaceView<'a, T>> {
+        self.surfaces().flat_map(|surf| surf.faces())
+    }
+
+    pub fn bounding_box(&self) -> Option<BoundingBox<T>> {
+        self.mesh
+            .get_group_bounding_box(self.object_index, self.index)
+    }
+}
+
+#[allow(dead_code)]
+impl<'a, T: Float + FromPrimitive> SurfaceView<'a, T> {
+    // マテリアル番号(-1 なら未指定/未解決)
+    pub fn material_index(&self) -> i32 {
+        self.surface.material_index
+    }
+
+    // usemtl で指定された名前
+    pub fn material_name(&self) -> &'a str {
+        &self.surface.material_name
+    }
+
+    // マテリアル(未指定/未解決なら既定のマテリアル)
+    pub fn material(&self) -> &'a Material<T> {
+        self.mesh.get_matrial(self.surface.material_index)
+    }
+
+    pub fn faces(&self) -> impl Iterator<Item = FaceView<'a, T>> {
+        let mesh = self.mesh;
+        self.surface
+            .faces
+            .iter()
+            .map(move |face| FaceView { mesh, face })
+    }
+}
+
+#[allow(dead_code)]
+impl<'a, T: Float + FromPrimitive> FaceView<'a, T> {
+    // スムージンググループ(0ならフラット)
+    pub fn smoothing_group(&self) -> u32 {
+        self.face.smoothing_group
+    }
+
+    pub fn len(&self) -> usize {
+        self.face.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.face.points.is_empty()
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = PointView<'a, T>> {
+        let mesh = self.mesh;
+        self.face
+            .points
+            .iter()
+            .map(move |point| PointView { mesh, point })
+    }
+
+    // 三角形分割した点の組(get_vertex_array と同じ分割)
+    pub fn triangles(&self) -> Vec<[PointView<'a, T>; 3]> {
+        let (mesh, face) = (self.mesh, self.face);
+        face.triangulate(&mesh.vertexes)
+            .into_iter()
+            .map(|triangle| {
+                triangle.map(|i| PointView {
+                    mesh,
+                    point: &face.points[i],
+                })
+            })
+            .collect()
+    }
+
+    // 面の法線(正規化済み)
+    pub fn normal(&self) -> Vecter3D<T> {
+        self.face.area_normal(&self.mesh.vertexes).normalize()
+    }
+}
+
+#[allow(dead_code)]
+impl<'a, T: Float + FromPrimitive> PointView<'a, T> {
+    // 各インデックス(0始まり、-1 なら未指定)
+    pub fn vertex_index(&self) -> i32 {
+        self.point.vertex_index
+    }
+
+    pub fn normal_index(&self) -> i32 {
+        self.point.normal_index
+    }
+
+    pub fn texture_coordinate_index(&self) -> i32 {
+        self.point.texture_coordinate_index
+    }
+
+    pub fn tangent_index(&self) -> i32 {
+        self.point.tangent_index
+    }
+
+    // 解決済みの値(未指定や範囲外なら None)
+    pub fn position(&self) -> Option<&'a Vecter3D<T>> {
+        lookup(&self.mesh.vertexes, self.point.vertex_index)
+    }
+
+    pub fn normal(&self) -> Option<&'a Vecter3D<T>> {
+        lookup(&self.mesh.normals, self.point.normal_index)
+    }
+
+    pub fn texture_coordinate(&self) -> Option<&'a Texture2D<T>> {
+        lookup(
+            &self.mesh.texture_coordinates,
+            self.point.texture_coordinate_index,
+        )
+    }
+
+    pub fn tangent(&self) -> Option<&'a Vecter4D<T>> {
+        lookup(&self.mesh.tangents, self.point.tangent_index)
+    }
+}
+
+// インデックスで要素を取得(負なら None)
+fn lookup<V>(items: &[V], index: i32) -> Option<&V> {
+    if index < 0 {
+        return None;
+    }
+    items.get(index as usize)
+}
@@ -0,0 +1,286 @@
+use super::{Material, Mesh, MeshError, Point, TextureMap, Vecter3D};
+use num_traits::{Float, FromPrimitive, ToPrimitive};
+use std::fmt::Display;
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::io::BufWriter;
+use std::str::FromStr;
+
+// 面の1点分の書式 (v, v/vt, v//vn, v/vt/vn)
+fn format_point(point: &Point) -> String {
+    let v = point.vertex_index + 1;
+    let vt = point.texture_coordinate_index + 1;
+    let vn = point.normal_index + 1;
+    match (vt > 0, vn > 0) {
+        (false, false) => format!("{}", v),
+        (true, false) => format!("{}/{}", v, vt),
+        (false, true) => format!("{}//{}", v, vn),
+        (true, true) => format!("{}/{}/{}", v, vt, vn),
+    }
+}
+
+#[allow(dead_code)]
+impl<T: FromStr + Float + FromPrimitive + ToPrimitive + Display> Mesh<T> {
+    // OBJ ファイルとして保存
+    pub fn save(&self, filename: &str) -> Result<(), MeshError> {
+        let to_error = |e| MeshError::Io {
+            path: filename.to_string(),
+            source: e,
+        };
+        let f = File::create(filename).map_err(to_error)?;
+        let mut writer = BufWriter::new(f);
+        self.write_obj(&mut writer).map_err(to_error)?;
+        writer.flush().map_err(to_error)
+    }
+
+    // OBJ 形式で書き出し
+    pub fn write_obj<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        if !self.material_libraries.is_empty() {
+            writeln!(writer, "mtllib {}", self.material_libraries.join(" "))?;
+        }
+
+        // 頂点データ
+        for v in &self.vertexes {
+            writeln!(writer, "v {} {} {}", v.x, v.y, v.z)?;
+        }
+        for vt in &self.texture_coordinates {
+            writeln!(writer, "vt {} {}", vt.u, vt.v)?;
+        }
+        for vn in &self.normals {
+            writeln!(writer, "vn {} {} {}", vn.x, vn.y, vn.z)?;
+        }
+
+        // 面データ
+        let mut material_name = "";
+        let mut smoothing_group = 0;
+        for obj in &self.objects {
+            if !obj.object_name.is_empty() {
+                writeln!(writer, "o {}", obj.object_name)?;
+            }
+            for grp in &obj.groups {
+                if !grp.group_name.is_empty() {
+                    writeln!(writer, "g {}", grp.group_name)?;
+                }
+                for surf in &grp.surfaces {
+                    if !surf.material_name.is_empty() && surf.material_name != material_name {
+                        writeln!(writer, "usemtl {}", surf.material_name)?;
+                        material_name = &surf.material_name;
+                    }
+                    for face in &surf.faces {
+                        if face.smoothing_group != smoothing_group {
+                            if face.smoothing_group == 0 {
+                                writeln!(writer, "s off")?;
+                            } else {
+                                writeln!(writer, "s {}", face.smoothing_group)?;
+                            }
+                            smoothing_group = face.smoothing_group;
+                        }
+                        let points: Vec<String> = face.points.iter().map(format_point).collect();
+                        writeln!(writer, "f {}", points.join(" "))?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // マテリアルを MTL ファイルとして保存
+    pub fn save_mtl(&self, filename: &str) -> Result<(), MeshError> {
+        let to_error = |e| MeshError::Io {
+            path: filename.to_string(),
+            source: e,
+        };
+        let f = File::create(filename).map_err(to_error)?;
+        let mut writer = BufWriter::new(f);
+        write_mtl(&self.materials, &mut writer).map_err(to_error)?;
+        writer.flush().map_err(to_error)
+    }
+}
+
+// 色の書き出し
+fn write_color<W: Write, T: FromPrimitive + Display>(
+    writer: &mut W,
+    command: &str,
+    color: &Vecter3D<T>,
+) -> io::Result<()> {
+    writeln!(writer, "{} {} {} {}", command, color.x, color.y, color.z)
+}
+
+// 省略可能な値の書き出し(0 なら未指定として何もしない)
+fn write_scalar<W: Write, T: FromPrimitive + Display + PartialEq>(
+    writer: &mut W,
+    command: &str,
+    value: T,
+) -> io::Result<()> {
+    if value == T::from_f64(0.0).unwrap() {
+        return Ok(());
+    }
+    writeln!(writer, "{} {}", command, value)
+}
+
+// 省略可能な色の書き出し(全て 0 なら何もしない)
+fn write_optional_color<W: Write, T: FromPrimitive + Display + PartialEq>(
+    writer: &mut W,
+    command: &str,
+    color: &Vecter3D<T>,
+) -> io::Result<()> {
+    let zero = T::from_f64(0.0).unwrap();
+    if color.x == zero && color.y == zero && color.z == zero {
+        return Ok(());
+    }
+    write_color(writer, command, color)
+}
+
+// テクスチャマップの書き出し(未指定なら何もしない、オプションは既定値以外のみ)
+fn write_map<W: Write, T: FromPrimitive + Display + PartialEq>(
+    writer: &mut W,
+    command: &str,
+    map: &TextureMap<T>,
+) -> io::Result<()> {
+    if map.empty() {
+        return Ok(());
+    }
+    let default = TextureMap::<T>::new();
+    let switch = |on: bool| if on { "on" } else { "off" };
+    write!(writer, "{}", command)?;
+    if map.blend_u != default.blend_u {
+        write!(writer, " -blendu {}", switch(map.blend_u))?;
+    }
+    if map.blend_v != default.blend_v {
+        write!(writer, " -blendv {}", switch(map.blend_v))?;
+    }
+    if map.color_correction != default.color_correction {
+        write!(writer, " -cc {}", switch(map.color_correction))?;
+    }
+    if map.clamp != default.clamp {
+        write!(writer, " -clamp {}", switch(map.clamp))?;
+    }
+    if map.bump_multiplier != default.bump_multiplier {
+        write!(writer, " -bm {}", map.bump_multiplier)?;
+    }
+    if map.boost != default.boost {
+        write!(writer, " -boost {}", map.boost)?;
+    }
+    if map.brightness != default.brightness || map.contrast != default.contrast {
+        write!(writer, " -mm {} {}", map.brightness, map.contrast)?;
+    }
+    if !map.channel.is_empty() {
+        write!(writer, " -imfchan {}", map.channel)?;
+    }
+    if map.resolution != default.resolution {
+        write!(writer, " -texres {}", map.resolution)?;
+    }
+    for (option, value, base) in [
+        ("-s", &map.scale, &default.scale),
+        ("-o", &map.offset, &default.offset),
+        ("-t", &map.turbulence, &default.turbulence),
+    ] {
+        if value != base {
+            write!(writer, " {} {} {} {}", option, value.x, value.y, value.z)?;
+        }
+    }
+    writeln!(writer, " {}", map.filename)
+}
+
+// MTL 形式で書き出し
+#[allow(dead_code)]
+pub fn write_mtl<W: Write, T: FromPrimitive + Display + PartialEq + Copy>(
+    materials: &[Material<T>],
+    writer: &mut W,
+) -> io::Result<()> {
+    for (i, mat) in materials.iter().enumerate() {
+        if i > 0 {
+            writeln!(writer)?;
+        }
+        writeln!(writer, "newmtl {}", mat.material_name)?;
+        write_color(writer, "Ka", &mat.ambient)?;
+        write_color(writer, "Kd", &mat.diffuse)?;
+        write_color(writer, "Ks", &mat.specular)?;
+        writeln!(writer, "Ns {}", mat.shininess)?;
+        writeln!(writer, "d {}", mat.alpha)?;
+        write_optional_color(writer, "Ke", &mat.emissive)?;
+        write_scalar(writer, "Ni", mat.optical_density)?;
+        write_optional_color(writer, "Tf", &mat.transmission_filter)?;
+        if mat.illumination_model != 0 {
+            writeln!(writer, "illum {}", mat.illumination_model)?;
+        }
+        write_scalar(writer, "Pr", mat.roughness)?;
+        write_scalar(writer, "Pm", mat.metallic)?;
+        write_scalar(writer, "Ps", mat.sheen)?;
+        write_scalar(writer, "Pc", mat.clearcoat_thickness)?;
+        write_scalar(writer, "Pcr", mat.clearcoat_roughness)?;
+        write_scalar(writer, "aniso", mat.anisotropy)?;
+        write_scalar(writer, "anisor", mat.anisotropy_rotation)?;
+        write_map(writer, "map_Ka", &mat.ambient_map)?;
+        write_map(writer, "map_Kd", &mat.diffuse_map)?;
+        write_map(writer, "map_Ks", &mat.specular_map)?;
+        write_map(writer, "map_Ke", &mat.emissive_map)?;
+        write_map(writer, "map_Ns", &mat.shininess_map)?;
+        write_map(writer, "map_d", &mat.alpha_map)?;
+        write_map(writer, "bump", &mat.bump_map)?;
+        write_map(writer, "disp", &mat.displacement_map)?;
+        write_map(writer, "decal", &mat.decal_map)?;
+        write_map(writer, "norm", &mat.normal_map)?;
+        write_map(writer, "map_Pr", &mat.roughness_map)?;
+        write_map(writer, "map_Pm", &mat.metallic_map)?;
+        write_map(writer, "map_Ps", &mat.sheen_map)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const MTL: &str = "newmtl body
+Ka 0.1 0.2 0.3
+Kd 0.5 0.25 0.125
+Ks 1 1 1
+Ke 0.5 0 0
+Ns 32
+d 0.5
+Ni 1.45
+Tf 0.9 0.8 0.7
+illum 2
+Pr 0.25
+Pm 0.75
+Ps 0.5
+Pc 0.125
+Pcr 0.375
+aniso 0.5
+anisor 0.25
+map_Kd -s 2 2 1 -o 0.5 0 0 -clamp on body.png
+map_Bump -bm 0.5 -imfchan l bump.png
+map_Pr -mm 0.1 0.9 -texres 512 rough.png
+
+newmtl face
+Kd 1 0.5 0.5
+map_d -blendu off -cc on alpha.png
+";
+
+    fn load(mtl: &str) -> Vec<Material<f32>> {
+        Mesh::<f32>::load_materials(Cursor::new(mtl), "test.mtl").unwrap()
+    }
+
+    #[test]
+    fn mtl_round_trip() {
+        let materials = load(MTL);
+        assert_eq!(materials.len(), 2);
+
+        let mut output = Vec::new();
+        write_mtl(&materials, &mut output).unwrap();
+        let reloaded = load(&String::from_utf8(output).unwrap());
+        assert_eq!(reloaded, materials);
+
+        let body = &reloaded[0];
+        assert_eq!(body.metallic, 0.75);
+        assert_eq!(body.diffuse_map.scale.x, 2.0);
+        assert!(body.diffuse_map.clamp);
+        assert_eq!(body.bump_map.bump_multiplier, 0.5);
+        assert_eq!(body.bump_map.channel, "l");
+        assert_eq!(body.roughness_map.resolution, 512);
+        assert!(!reloaded[1].alpha_map.blend_u);
+    }
+}